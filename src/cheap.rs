@@ -0,0 +1,1320 @@
+use crate::counter::{CheapOp, Counter, Sift};
+use crate::error::CheapError;
+use crate::order::{ByKey, ByOrdering, NaturalOrder, Order};
+use crate::sort::is_sorted_with;
+use std::cmp::Ordering;
+use std::fmt;
+
+/*
+ * The left child in a centered heap is either center - 1, or twice the distance from center.
+ * Uses usize::MAX for known out of bounds as we expect that will fail a bounds check.
+ */
+#[inline]
+pub(crate) fn get_left_child(x: usize, c: usize) -> usize {
+    if x == c {
+        if c == 0 {
+            usize::MAX
+        } else {
+            c - 1
+        }
+    } else if x > c {
+        c + (x - c) * 2
+    } else {
+        let o = (c - x) * 2;
+        if o > c {
+            usize::MAX
+        } else {
+            c - o
+        }
+    }
+}
+
+/*
+ * The right child in a centered heap is either center + 1, or twice the distance from center
+ * plus one.
+ * Uses usize::MAX for known out of bounds as we expect that will fail a bounds check.
+ */
+#[inline]
+pub(crate) fn get_right_child(x: usize, c: usize) -> usize {
+    if x == c {
+        c + 1
+    } else if x > c {
+        (x - c) * 2 + 1 + c
+    } else {
+        let o = (c - x) * 2 + 1;
+        if o > c {
+            usize::MAX
+        } else {
+            c - o
+        }
+    }
+}
+
+/*
+ * The parent node is half the distance from the center, rounded down.
+ */
+#[inline]
+pub(crate) fn get_parent(x: usize, c: usize) -> usize {
+    debug_assert!(x != c, "cheap-state: can't find parent of center node");
+    if x > c {
+        c + (x - c) / 2
+    } else {
+        c - (c - x) / 2
+    }
+}
+
+/*
+ * The recenter limit identifies where nodes must be sifted in order to fully recenter.
+ * This is half the distance from the center, rounded up.
+ */
+#[inline]
+pub(crate) fn get_recenter_limit(x: usize, c: usize) -> usize {
+    if x > c {
+        (x - c).div_ceil(2) + c
+    } else {
+        c - (c - x).div_ceil(2)
+    }
+}
+
+/**
+ * A centered heap over a borrowed slice.
+ *
+ * The heap occupies `a[lo..hi]` with its best element at `a[c]`. Everything outside that range
+ * is left alone except where an operation explicitly moves a value across the boundary.
+ * Every compare and swap is reported to the counter.
+ *
+ * Elements are ranked by an `Order`, which defaults to `PartialOrd`. Use `with_order` to supply
+ * a different one.
+ */
+pub struct Cheap<'a, E: fmt::Debug, C: Counter + fmt::Debug, O = NaturalOrder> {
+    a: &'a mut [E],
+    lo: usize,
+    c: usize,
+    hi: usize,
+    // Where `a` starts in the slice the heap was built over, so the counter sees the caller's
+    // indices once `into_sorted_iter` has split off the front.
+    at: usize,
+    cnt: &'a mut C,
+    ord: O,
+}
+
+/// The descriptive name for [`Cheap`].
+pub type CenteredHeap<'a, E, C, O = NaturalOrder> = Cheap<'a, E, C, O>;
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug> Cheap<'a, E, C> {
+    /// Construct a c-heap oriented at the left end of the array.
+    pub fn new_left(a: &'a mut [E], cnt: &'a mut C) -> Self {
+        Cheap {
+            a,
+            lo: 0,
+            c: 0,
+            hi: 0,
+            at: 0,
+            cnt,
+            ord: NaturalOrder,
+        }
+    }
+
+    /// Construct a c-heap oriented at the right end of the array.
+    pub fn new_right(a: &'a mut [E], cnt: &'a mut C) -> Self {
+        let i = a.len();
+        Cheap {
+            a,
+            lo: i,
+            c: i,
+            hi: i,
+            at: 0,
+            cnt,
+            ord: NaturalOrder,
+        }
+    }
+
+    /// Construct a c-heap spanning the whole array, centered at the left.
+    ///
+    /// The heap is not valid until `recenter` is called.
+    pub fn new_spanleft(a: &'a mut [E], cnt: &'a mut C) -> Self {
+        let i = a.len();
+        Cheap {
+            a,
+            lo: 0,
+            c: 0,
+            hi: i,
+            at: 0,
+            cnt,
+            ord: NaturalOrder,
+        }
+    }
+
+    /// Construct a c-heap spanning the whole array, centered at the right.
+    ///
+    /// The heap is not valid until `recenter` is called.
+    pub fn new_spanright(a: &'a mut [E], cnt: &'a mut C) -> Self {
+        let i = a.len();
+        Cheap {
+            a,
+            lo: 0,
+            c: i.saturating_sub(1),
+            hi: i,
+            at: 0,
+            cnt,
+            ord: NaturalOrder,
+        }
+    }
+
+    /// Construct a c-heap over `a[lo..hi]` centered at `c`.
+    ///
+    /// An empty range must have `lo == c == hi`. The heap is not valid until `recenter` is
+    /// called, unless the range is empty or already arranged as a c-heap.
+    pub fn new_range(a: &'a mut [E], lo: usize, c: usize, hi: usize, cnt: &'a mut C) -> Self {
+        let ch = Cheap {
+            a,
+            lo,
+            c,
+            hi,
+            at: 0,
+            cnt,
+            ord: NaturalOrder,
+        };
+        ch.check_range();
+        ch
+    }
+}
+
+impl<'a, E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug> Cheap<'a, E, C> {
+    /**
+     * Given lo:md is sorted and md:hi is sorted, merge them.
+     *
+     * Uses a centered heap.
+     *
+     * ```text
+     * ---|------|------|-----|---
+     *    lo   ch.lo  ch.hi  hi
+     * ```
+     */
+    pub fn merge(a: &mut [E], lo: usize, md: usize, hi: usize, cnt: &mut C) {
+        Cheap::merge_with(a, lo, md, hi, &mut NaturalOrder, cnt)
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O> Cheap<'a, E, C, O> {
+    /// Replace the order used to rank elements.
+    ///
+    /// The heap is not revalidated; recenter it if it isn't empty.
+    pub fn with_order<O2>(self, ord: O2) -> Cheap<'a, E, C, O2> {
+        Cheap {
+            a: self.a,
+            lo: self.lo,
+            c: self.c,
+            hi: self.hi,
+            at: self.at,
+            cnt: self.cnt,
+            ord,
+        }
+    }
+
+    /// Get the markers as `(lo, c, hi)`.
+    #[inline]
+    pub fn params(&self) -> (usize, usize, usize) {
+        (self.lo, self.c, self.hi)
+    }
+
+    /// The low marker; the first index inside the heap.
+    #[inline]
+    pub fn lo(&self) -> usize {
+        self.lo
+    }
+
+    /// The center marker; the index of the best element.
+    #[inline]
+    pub fn center(&self) -> usize {
+        self.c
+    }
+
+    /// The high marker; one past the last index inside the heap.
+    #[inline]
+    pub fn hi(&self) -> usize {
+        self.hi
+    }
+
+    /// Number of elements inside the heap.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.hi - self.lo
+    }
+
+    /// The best element, if the heap isn't empty.
+    pub fn peek(&self) -> Option<&E> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(&self.a[self.c])
+        }
+    }
+
+    /// The whole underlying slice, including the parts outside the heap.
+    pub fn as_slice(&self) -> &[E] {
+        self.a
+    }
+
+    /// Whether the heap holds no elements.
+    pub fn is_empty(&self) -> bool {
+        debug_assert!(self.lo <= self.hi, "c-heap state error: markers invalid");
+        self.lo == self.hi
+    }
+
+    /// Check only the range invariants, panicking if they fail.
+    pub fn check_range(&self) {
+        let (lo, c, hi) = self.params();
+        assert!(
+            /* 0 <= lo && */ hi <= self.a.len(),
+            "c-heap state: markers outside array"
+        );
+        assert!(
+            lo == c && c == hi || lo <= c && c <= hi,
+            "c-heap state: markers invalid"
+        );
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> Cheap<'a, E, C, O> {
+    #[inline]
+    fn swap(&mut self, i: usize, j: usize) {
+        self.cnt.count_swap_at(self.at + i, self.at + j);
+        self.a.swap(i, j);
+    }
+
+    // Check if a[i] is "better than" a[j].
+    #[inline]
+    fn bt(&mut self, i: usize, j: usize) -> bool {
+        self.cnt.count_compare();
+        self.ord.le(&self.a[i], &self.a[j])
+    }
+
+    // Check if a[i] is "better than" a[j].
+    #[inline]
+    fn bt_nocount(&mut self, i: usize, j: usize) -> bool {
+        self.ord.le(&self.a[i], &self.a[j])
+    }
+
+    // Run `f` as the operation `op` given the index `arg`, so the counter can attribute its work.
+    #[inline]
+    fn op<T>(&mut self, op: CheapOp, arg: Option<usize>, f: impl FnOnce(&mut Self) -> T) -> T {
+        let at = self.at;
+        self.cnt
+            .enter(op, self.reported_params(), arg.map(|i| at + i));
+        let r = f(self);
+        self.cnt.leave(op, self.reported_params());
+        r
+    }
+
+    // The markers as the counter should see them, relative to the slice the heap was built over.
+    #[inline]
+    fn reported_params(&self) -> (usize, usize, usize) {
+        (self.at + self.lo, self.at + self.c, self.at + self.hi)
+    }
+
+    // Do a full check of the invariants, panicking if they fail.
+    #[allow(dead_code)]
+    fn check(&mut self) {
+        self.check_range();
+        assert!(self.is_valid(), "c-heap state: heap invariant failed");
+    }
+
+    // Check the heap invariant: every parent is better than its children.
+    pub(crate) fn is_valid(&mut self) -> bool {
+        let (lo, c, hi) = self.params();
+        for i in lo..hi {
+            if i != c {
+                let p = get_parent(i, c);
+                let x = self.bt_nocount(p, i);
+                if !x {
+                    show_call!(
+                        self,
+                        "check: failed(a[{}]={:?} bt a[{}]={:?}, ",
+                        p,
+                        &self.a[p],
+                        i,
+                        &self.a[i]
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /**
+     * Recentering initializes the heap at a range from lo to (but not including) hi
+     * and with a center at c.
+     *
+     * After running this, a valid center heap exists as follows:
+     *
+     * ```text
+     *     . . . [x, x, x, C, x, x, x] . . .
+     *          lo^                    ^hi
+     * ```
+     *
+     * The best value (per bt) will be located at index c.
+     *
+     * Data outside the range `[lo:hi]` will be unaffected.
+     */
+    pub fn recenter(&mut self) {
+        self.op(CheapOp::Recenter, None, |ch| {
+            dbg_show_call!(ch, "recenter-start(");
+            #[cfg(debug_assertions)]
+            ch.check_range();
+            let (lo, c, hi) = ch.params();
+            let left = get_recenter_limit(lo, c)..c;
+            let right = c..get_recenter_limit(hi, c);
+            ch.cnt.count_recenter(left.len() + right.len());
+            for i in left {
+                ch.sift_out(i);
+            }
+            for i in right.rev() {
+                ch.sift_out(i);
+            }
+            #[cfg(debug_assertions)]
+            ch.check();
+            dbg_show_call!(ch, "recenter-end(");
+        })
+    }
+
+    /**
+     * Move a root node towards a leaf.
+     *
+     *   leaf   leaf
+     *       node
+     *
+     * We inspect each leaf, and if the leaf is "better than" the node, we'll swap them to preserve
+     * the invariant.
+     */
+    fn sift_out(&mut self, ii: usize) {
+        dbg_show_call!(self, "sift_out-start({}, ", ii);
+        let mut vio = 0;
+        let mut vio_found = true;
+        let mut n = ii;
+        let mut levels = 0;
+        let (lo, c, hi) = self.params();
+
+        while vio_found {
+            vio_found = false;
+
+            let ch1 = get_left_child(n, c);
+            if lo <= ch1 && ch1 < hi {
+                if self.bt(ch1, n) {
+                    vio = ch1;
+                    vio_found = true;
+                    dbg_println!("sift: left child {} is better than parent {}", ch1, n);
+                } else {
+                    dbg_println!("sift: parent {} is better than left child {}", n, ch1);
+                }
+            } else {
+                dbg_println!("sift: left child {} is out of range", ch1);
+            }
+
+            let ch2 = get_right_child(n, c);
+            if lo <= ch2 && ch2 < hi {
+                if self.bt(ch2, n) {
+                    dbg_println!("sift: right child {} is better than parent {}", ch2, n);
+                    if !vio_found || self.bt(ch2, vio) {
+                        dbg_println!("sift: and right child is better than left child");
+                        vio_found = true;
+                        vio = ch2;
+                    }
+                } else {
+                    dbg_println!("sift: parent {} is better than right child {}", n, ch2);
+                }
+            } else {
+                dbg_println!("sift: right child {} is out of range", ch2);
+            }
+            if vio_found {
+                dbg_println!("sift: swap {} and {}", n, vio);
+                self.swap(n, vio);
+                n = vio;
+                levels += 1;
+            }
+        }
+        self.cnt.count_sift(Sift::Out, levels);
+        dbg_show_call!(self, "sift_out-end({}, ", ii);
+    }
+
+    /*
+     * Move a leaf up towards the root.
+     */
+    fn sift_in(&mut self, i: usize) {
+        dbg_show_call!(self, "sift_in-start({}, ", i);
+        let mut p;
+        let mut n = i;
+        let c = self.c;
+        let mut levels = 0;
+        while n != c {
+            p = get_parent(n, c);
+            if self.bt(n, p) {
+                dbg_println!("sift_in: child {} better than parent {}", n, p);
+                // Violation: child is "better than" parent.
+                self.swap(n, p);
+                n = p;
+                levels += 1;
+            } else {
+                dbg_println!("sift_in: parent {} better than child {}, ending", p, n);
+                break;
+            }
+        }
+        self.cnt.count_sift(Sift::In, levels);
+        dbg_show_call!(self, "sift_in-end({}, ", i);
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      [x, x, C, x, x]
+     *       ^lo
+     * ```
+     *
+     * Swap the center (best) value into lo and shrinks the range on the left.
+     *
+     * ```text
+     *      C  [x, x, x, x]
+     * ```
+     *
+     * Side-effect: Adjust lo to be lo + 1.
+     *
+     * Side-effect: May re-center.
+     */
+    pub fn pop_left(&mut self) {
+        self.try_pop_left().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `pop_left`, but returns an error instead of panicking.
+    pub fn try_pop_left(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::PopLeft, None, |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            if ch.is_empty() {
+                return Err(CheapError::Empty);
+            }
+            ch.take_left();
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    // Pop the value at the center to the left. The rest of the c-heap must be valid.
+    fn take_left(&mut self) {
+        let lop = self.lo + 1;
+        if self.lo == self.c {
+            if lop < self.hi {
+                self.c = self.hi - 1;
+                self.lo = lop;
+                self.recenter();
+            } else {
+                self.lo = lop; // Now empty.
+                self.c = lop;
+            }
+        } else {
+            self.swap(self.c, self.lo);
+            self.lo = lop;
+            self.sift_out(self.c);
+        }
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      [x, x, C, x, x] . . .
+     *                      ^hi
+     * ```
+     *
+     * Swap the center (best) value into hi - 1 and shrinks the range on the right.
+     *
+     * ```text
+     *      [x, x, x, x] C . . .
+     *                   ^hi
+     * ```
+     *
+     * Side-effect: Adjusts hi to be hi - 1.
+     *
+     * Side-effect: May re-center.
+     */
+    pub fn pop_right(&mut self) {
+        self.try_pop_right().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `pop_right`, but returns an error instead of panicking.
+    pub fn try_pop_right(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::PopRight, None, |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            if ch.is_empty() {
+                return Err(CheapError::Empty);
+            }
+            ch.take_right();
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    // Pop the value at the center to the right. The rest of the c-heap must be valid.
+    fn take_right(&mut self) {
+        let hip = self.hi - 1;
+        if hip == self.c {
+            self.c = self.lo;
+            self.hi = hip;
+            if self.lo < hip {
+                self.recenter();
+            } // else now empty.
+        } else {
+            self.swap(hip, self.c);
+            self.hi = hip;
+            self.sift_out(self.c);
+        }
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      L [x, x, x, x]
+     *         ^lo
+     * ```
+     *
+     * Expand the range to absorb L and preserve invariants.
+     *
+     * ```text
+     *     [x, x, x, L, x]
+     * ```
+     *
+     * Side-effect: adjust lo to lo - 1.
+     *
+     * Side-effect: may adjust center index when pushing into an empty container.
+     */
+    pub fn push_left(&mut self) {
+        self.try_push_left().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `push_left`, but returns an error instead of panicking.
+    pub fn try_push_left(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::PushLeft, None, |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            if ch.lo == 0 {
+                return Err(CheapError::OutOfBounds);
+            }
+            let lop = ch.lo - 1;
+            if ch.c == ch.hi {
+                debug_assert!(ch.lo == ch.c, "c-heap state: expected an empty c-heap");
+                ch.c = lop;
+            }
+            ch.lo = lop;
+            ch.sift_in(lop);
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /// Swap the value at `i` into `lo - 1`, then `push_left` it.
+    pub fn push_left_swap(&mut self, i: usize) {
+        self.try_push_left_swap(i)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `push_left_swap`, but returns an error instead of panicking.
+    pub fn try_push_left_swap(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::PushLeftSwap, Some(i), |ch| {
+            if i >= ch.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            if i >= ch.lo && i < ch.hi {
+                return Err(CheapError::IndexInsideHeap);
+            }
+            if ch.lo == 0 {
+                return Err(CheapError::OutOfBounds);
+            }
+            ch.swap(i, ch.lo - 1);
+            ch.try_push_left()
+        })
+    }
+
+    /// Swap the value at `i` into `hi`, then `push_right` it.
+    pub fn push_right_swap(&mut self, i: usize) {
+        self.try_push_right_swap(i)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `push_right_swap`, but returns an error instead of panicking.
+    pub fn try_push_right_swap(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::PushRightSwap, Some(i), |ch| {
+            if i >= ch.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            if i >= ch.lo && i < ch.hi {
+                return Err(CheapError::IndexInsideHeap);
+            }
+            if ch.hi >= ch.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            ch.swap(i, ch.hi);
+            ch.try_push_right()
+        })
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *     [x, x, x, x] R
+     *                hi^
+     * ```
+     *
+     * Expand the range to absorb R and preserve invariants.
+     *
+     * ```text
+     *     [x, R, x, x, x]
+     * ```
+     *
+     * Side-effect: adjust lo to lo - 1.
+     *
+     * Side-effect: may adjust center index when pushing into an empty container.
+     */
+    pub fn push_right(&mut self) {
+        self.try_push_right().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `push_right`, but returns an error instead of panicking.
+    pub fn try_push_right(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::PushRight, None, |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            if ch.hi >= ch.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            let hip = ch.hi + 1;
+            ch.sift_in(ch.hi);
+            ch.hi = hip;
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      [x, x, C, x, x] . . . . i
+     * ```
+     *
+     * Swap the value at i with the value at C and preserve invariants.
+     *
+     * ```text
+     *      [x, x, i, x, x] . . . . C
+     * ```
+     *
+     * This is equivalent to saving the value at index i, popping the best value into i,
+     * and then pushing the saved value back into the heap.
+     *
+     * After this operation, the value at i will always be drawn from the c-heap.
+     *
+     * These semantics mean it does not work with an empty c-heap.
+     *
+     * Guarantees no change to the range.
+     */
+    pub fn poppush(&mut self, i: usize) {
+        self.try_poppush(i).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `poppush`, but returns an error instead of panicking.
+    pub fn try_poppush(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::Poppush, Some(i), |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            dbg_show_call!(ch, "poppush(i={}, ", i);
+            // We could do nothing, but the caller is expecting the best value from the c-heap.
+            if ch.is_empty() {
+                return Err(CheapError::Empty);
+            }
+            if i >= ch.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            if i >= ch.lo && i < ch.hi {
+                return Err(CheapError::IndexInsideHeap);
+            }
+            ch.swap(i, ch.c);
+            ch.sift_out(ch.c);
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      [x, x, C, x, x] . . . . i
+     * ```
+     *
+     * Compare the values at C and i. If i is better than C, do nothing.
+     *
+     * Otherwise, swap the value at i with the value at C and preserve invariants.
+     *
+     * ```text
+     *      [x, x, i, x, x] . . . . C
+     * ```
+     *
+     * This is equivalent to pushing i's value into the heap, and then popping the best value
+     * from the heap.
+     *
+     * These semantics mean that nothing will happen if the c-heap is empty or if i is already
+     * better than a value on the c-heap.
+     *
+     * Guarantees no change to the range.
+     */
+    pub fn pushpop(&mut self, i: usize) {
+        self.try_pushpop(i).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `pushpop`, but returns an error instead of panicking.
+    pub fn try_pushpop(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::Pushpop, Some(i), |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            dbg_show_call!(ch, "pushpop(i={}, ", i);
+            if i >= ch.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            if i >= ch.lo && i < ch.hi {
+                return Err(CheapError::IndexInsideHeap);
+            }
+            if ch.is_empty() || ch.bt(i, ch.c) {
+                return Ok(());
+            }
+            ch.swap(i, ch.c);
+            ch.sift_out(ch.c);
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      [x, x, x, x, x] R
+     * ```
+     *
+     * Transfer the right-hand value over to the left:
+     *
+     * ```text
+     *      R [x, x, x, x, x]
+     * ```
+     *
+     * Side-effect: adjusts lo and hi to be lo + 1 and hi + 1.
+     *
+     * Side-effect: may recenter the heap.
+     */
+    pub fn slide_right(&mut self) {
+        self.try_slide_right().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `slide_right`, but returns an error instead of panicking.
+    pub fn try_slide_right(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::SlideRight, None, |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            if ch.hi >= ch.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            if ch.is_empty() {
+                ch.lo += 1;
+                ch.c += 1;
+                ch.hi += 1;
+            } else {
+                let lop = ch.lo + 1;
+                let hip = ch.hi + 1;
+                ch.swap(ch.lo, ch.hi);
+                if ch.c == ch.lo {
+                    ch.c = ch.hi;
+                    ch.lo = lop;
+                    ch.hi = hip;
+                    ch.recenter();
+                } else {
+                    ch.sift_in(ch.hi);
+                    ch.lo = lop;
+                    ch.hi = hip;
+                }
+            }
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      L [x, x, x, x, x]
+     * ```
+     *
+     * Transfer the left-hand value over to the right:
+     *
+     * ```text
+     *      [x, x, x, x, x] L
+     * ```
+     *
+     * Side-effect: adjusts lo and hi to be lo - 1 and hi - 1.
+     *
+     * Side-effect: may recenter the heap.
+     */
+    pub fn slide_left(&mut self) {
+        self.try_slide_left().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `slide_left`, but returns an error instead of panicking.
+    pub fn try_slide_left(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::SlideLeft, None, |ch| {
+            #[cfg(debug_assertions)]
+            ch.check();
+            if ch.lo == 0 {
+                return Err(CheapError::OutOfBounds);
+            }
+            if ch.is_empty() {
+                ch.lo -= 1;
+                ch.c -= 1;
+                ch.hi -= 1;
+            } else {
+                let lop = ch.lo - 1;
+                let hip = ch.hi - 1;
+                ch.swap(lop, hip);
+                if ch.c == hip {
+                    // The old lo is outside the new range when there's only one element.
+                    ch.c = lop;
+                    ch.lo = lop;
+                    ch.hi = hip;
+                    ch.recenter();
+                } else {
+                    ch.sift_in(lop);
+                    ch.lo = lop;
+                    ch.hi = hip;
+                }
+            }
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      [x, x, C, x, i, x]
+     * ```
+     *
+     * Take the value at i out of the c-heap, leaving it just outside the low end:
+     *
+     * ```text
+     *      i [x, x, C, x, x]
+     * ```
+     *
+     * The value is first carried up to the center without comparing it to anything, as if it
+     * were better than its ancestors, and is then popped like the best value.
+     *
+     * Side-effect: adjusts lo to lo + 1.
+     */
+    pub fn remove_left(&mut self, i: usize) {
+        self.try_remove_left(i).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `remove_left`, but returns an error instead of panicking.
+    pub fn try_remove_left(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::RemoveLeft, Some(i), |ch| {
+            if i < ch.lo || i >= ch.hi {
+                return Err(CheapError::IndexOutsideHeap);
+            }
+            #[cfg(debug_assertions)]
+            ch.check();
+            ch.raise(i);
+            ch.take_left();
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Given our range:
+     *
+     * ```text
+     *      [x, i, x, C, x, x]
+     * ```
+     *
+     * Take the value at i out of the c-heap, leaving it just outside the high end:
+     *
+     * ```text
+     *      [x, x, C, x, x] i
+     * ```
+     *
+     * Side-effect: adjusts hi to hi - 1.
+     */
+    pub fn remove_right(&mut self, i: usize) {
+        self.try_remove_right(i).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `remove_right`, but returns an error instead of panicking.
+    pub fn try_remove_right(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::RemoveRight, Some(i), |ch| {
+            if i < ch.lo || i >= ch.hi {
+                return Err(CheapError::IndexOutsideHeap);
+            }
+            #[cfg(debug_assertions)]
+            ch.check();
+            ch.raise(i);
+            ch.take_right();
+            #[cfg(debug_assertions)]
+            ch.check();
+            Ok(())
+        })
+    }
+
+    /*
+     * Swap the value at i up the path to the center. Each value on the path moves down one
+     * level, below a value it was already better than, so only the center may be out of place.
+     */
+    fn raise(&mut self, i: usize) {
+        let mut n = i;
+        while n != self.c {
+            let p = get_parent(n, self.c);
+            self.swap(n, p);
+            n = p;
+        }
+    }
+
+    /**
+     * Pop the best values to the left one at a time, yielding the index each one lands at.
+     *
+     * The indices count up from the current `lo`, and the values there come out in order.
+     * Dropping the iterator early leaves the rest as a valid c-heap.
+     */
+    pub fn drain_left(&mut self) -> DrainLeft<'_, 'a, E, C, O> {
+        DrainLeft { ch: self }
+    }
+
+    /**
+     * Pop the best values to the right one at a time, yielding the index each one lands at.
+     *
+     * The indices count down from the current `hi - 1`. Dropping the iterator early leaves the
+     * rest as a valid c-heap.
+     */
+    pub fn drain_right(&mut self) -> DrainRight<'_, 'a, E, C, O> {
+        DrainRight { ch: self }
+    }
+
+    /**
+     * Pop every value to the left, yielding each one in order as it lands.
+     *
+     * Once popped, a slot is never touched again, so it's handed out for the rest of `'a`.
+     * The slots left of `lo` are not part of the heap and are never yielded.
+     */
+    pub fn into_sorted_iter(mut self) -> IntoSortedIter<'a, E, C, O> {
+        let lo = self.lo;
+        self.a = &mut std::mem::take(&mut self.a)[lo..];
+        self.at += lo;
+        self.lo -= lo;
+        self.c -= lo;
+        self.hi -= lo;
+        IntoSortedIter { ch: self }
+    }
+
+    /**
+     * Given lo:md is sorted and md:hi is sorted by `ord`, merge them.
+     *
+     * This has the signature `merge_sort_with` expects of a merge.
+     */
+    pub fn merge_with(a: &mut [E], lo: usize, md: usize, hi: usize, ord: &mut O, cnt: &mut C) {
+        dbg_println!("merge({}, {}, {})", lo, md, hi);
+        let mut ch = Cheap {
+            a,
+            lo: md,
+            c: md,
+            hi: md,
+            at: 0,
+            cnt,
+            ord,
+        };
+        ch.cnt.enter(CheapOp::Merge, (lo, md, hi), None);
+        debug_assert!(
+            is_sorted_with(ch.a, lo, md, &mut ch.ord),
+            "merge(pre): lo to md not sorted"
+        );
+        debug_assert!(
+            is_sorted_with(ch.a, md, hi, &mut ch.ord),
+            "merge(pre): md to hi not sorted"
+        );
+
+        for ix in lo..hi {
+            if ix >= ch.hi {
+                // Only one vector left, nothing to do.
+                break;
+            }
+
+            let mut best = MC::None;
+
+            if ix < ch.lo {
+                best = MC::Lo(&ch.a[ix]);
+            }
+            if ch.lo < ch.hi {
+                best = best.better(MC::Md(&ch.a[ch.c]), &mut ch.ord, ch.cnt);
+            }
+            if ch.hi < hi {
+                best = best.better(MC::Hi(&ch.a[ch.hi]), &mut ch.ord, ch.cnt);
+            }
+            if let MC::None = best {
+                panic!("merge: logic error");
+            }
+            if let MC::Lo(_) = best {
+                dbg_println!("merge: output is in place");
+                continue;
+            } else if ix < ch.lo {
+                if let MC::Md(_) = best {
+                    // Pop the best value from ch into ix, and push the value that was at ix in.
+                    dbg_println!("merge: poppush");
+                    ch.poppush(ix);
+                } else {
+                    // Swap the right hand value into ix
+                    dbg_println!("merge: push_right");
+                    ch.push_right_swap(ix);
+                }
+            } else if ix == ch.lo {
+                if let MC::Md(_) = best {
+                    // We're in ch, so just pop a value in place.
+                    dbg_println!("merge: pop_left");
+                    ch.pop_left();
+                } else {
+                    // We just need to move the right hand value into place.
+                    dbg_println!("merge: slide_right");
+                    ch.slide_right();
+                }
+            } else {
+                panic!("merge: ix is invalid!");
+            }
+        }
+        let params = ch.params();
+        ch.cnt.leave(CheapOp::Merge, params);
+        debug_assert!(
+            is_sorted_with(ch.a, lo, hi, &mut ch.ord),
+            "merge(post): not sorted after merge"
+        );
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, F: FnMut(&E, &E) -> Ordering>
+    Cheap<'a, E, C, ByOrdering<F>>
+{
+    /// Merge sorted runs `lo..md` and `md..hi` as ordered by `compare`.
+    pub fn merge_by(a: &mut [E], lo: usize, md: usize, hi: usize, compare: F, cnt: &mut C) {
+        Cheap::merge_with(a, lo, md, hi, &mut ByOrdering(compare), cnt)
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, K: Ord, F: FnMut(&E) -> K>
+    Cheap<'a, E, C, ByKey<F>>
+{
+    /// Merge sorted runs `lo..md` and `md..hi` as ordered by the keys `key` extracts.
+    pub fn merge_by_key(a: &mut [E], lo: usize, md: usize, hi: usize, key: F, cnt: &mut C) {
+        Cheap::merge_with(a, lo, md, hi, &mut ByKey(key), cnt)
+    }
+}
+
+/// Iterator returned by [`Cheap::drain_left`].
+pub struct DrainLeft<'h, 'a, E: fmt::Debug, C: Counter + fmt::Debug, O> {
+    ch: &'h mut Cheap<'a, E, C, O>,
+}
+
+impl<'h, 'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> Iterator
+    for DrainLeft<'h, 'a, E, C, O>
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.ch.is_empty() {
+            return None;
+        }
+        let i = self.ch.lo;
+        self.ch.pop_left();
+        Some(i)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ch.len(), Some(self.ch.len()))
+    }
+}
+
+impl<'h, 'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> ExactSizeIterator
+    for DrainLeft<'h, 'a, E, C, O>
+{
+}
+
+/// Iterator returned by [`Cheap::drain_right`].
+pub struct DrainRight<'h, 'a, E: fmt::Debug, C: Counter + fmt::Debug, O> {
+    ch: &'h mut Cheap<'a, E, C, O>,
+}
+
+impl<'h, 'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> Iterator
+    for DrainRight<'h, 'a, E, C, O>
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.ch.is_empty() {
+            return None;
+        }
+        let i = self.ch.hi - 1;
+        self.ch.pop_right();
+        Some(i)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ch.len(), Some(self.ch.len()))
+    }
+}
+
+impl<'h, 'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> ExactSizeIterator
+    for DrainRight<'h, 'a, E, C, O>
+{
+}
+
+/**
+ * Iterator returned by [`Cheap::into_sorted_iter`].
+ *
+ * The c-heap always starts at index 0 of its slice. Each value is popped into slot 0, which is
+ * then split off the front of the slice and yielded, and the markers shift down by one. Since a
+ * c-heap's shape only depends on distances from the center, the shift doesn't disturb it.
+ */
+pub struct IntoSortedIter<'a, E: fmt::Debug, C: Counter + fmt::Debug, O> {
+    ch: Cheap<'a, E, C, O>,
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> Iterator
+    for IntoSortedIter<'a, E, C, O>
+{
+    type Item = &'a mut E;
+
+    fn next(&mut self) -> Option<&'a mut E> {
+        if self.ch.is_empty() {
+            return None;
+        }
+        self.ch.pop_left();
+        let (first, rest) = std::mem::take(&mut self.ch.a).split_first_mut()?;
+        self.ch.a = rest;
+        self.ch.at += 1;
+        self.ch.lo -= 1;
+        self.ch.c -= 1;
+        self.ch.hi -= 1;
+        Some(first)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ch.len(), Some(self.ch.len()))
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> ExactSizeIterator
+    for IntoSortedIter<'a, E, C, O>
+{
+}
+
+// Convenience for formatting a single entity.
+macro_rules! one_ent {
+    ($self: ident, $i: expr, $f:ident) => {
+        $f.write_str(format!("{:?}", &$self.a[$i]).as_str())?;
+        let mut dot = ":";
+        if $i == $self.lo {
+            $f.write_str(":lo")?;
+            dot = ".";
+        }
+        if $i == $self.c {
+            $f.write_str(dot)?;
+            $f.write_str("c")?;
+            dot = ".";
+        }
+        if $i == $self.hi {
+            $f.write_str(dot)?;
+            $f.write_str("hi")?;
+        }
+    };
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O> fmt::Debug for Cheap<'a, E, C, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let a_len = self.a.len();
+
+        if a_len < 1 {
+            f.write_str("[]")?;
+        } else if a_len < 100 {
+            f.write_str("[")?;
+            one_ent!(self, 0, f);
+            for i in 1..a_len {
+                f.write_str(" ")?;
+                one_ent!(self, i, f);
+            }
+            f.write_str("]")?;
+        } else {
+            f.write_str("[")?;
+            one_ent!(self, 0, f);
+            for i in 1..40 {
+                f.write_str(" ")?;
+                one_ent!(self, i, f);
+            }
+            f.write_str(" ...")?;
+            for i in a_len - 40..a_len {
+                f.write_str(" ")?;
+                one_ent!(self, i, f);
+            }
+            f.write_str("]")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum MC<'a, E> {
+    None,      // -1
+    Lo(&'a E), // 0
+    Md(&'a E), // 1
+    Hi(&'a E), // 2
+}
+
+impl<'a, E> MC<'a, E> {
+    fn val(&self) -> Option<&'a E> {
+        match self {
+            MC::None => None,
+            MC::Lo(v) => Some(v),
+            MC::Md(v) => Some(v),
+            MC::Hi(v) => Some(v),
+        }
+    }
+
+    fn better<O: Order<E>, C: Counter>(self, other: Self, ord: &mut O, cnt: &mut C) -> Self {
+        match (self.val(), other.val()) {
+            (None, None) => MC::None,
+            (Some(_), None) => self,
+            (None, Some(_)) => other,
+            (Some(a), Some(b)) => {
+                cnt.count_compare();
+                if ord.lt(a, b) {
+                    self
+                } else {
+                    other
+                }
+            }
+        }
+    }
+}
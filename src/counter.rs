@@ -0,0 +1,250 @@
+use json::{object, JsonValue};
+use std::cmp::Ordering;
+
+/// The c-heap operations a `Counter` is told about, so it can attribute work to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheapOp {
+    Recenter,
+    PopLeft,
+    PopRight,
+    PushLeft,
+    PushRight,
+    PushLeftSwap,
+    PushRightSwap,
+    Poppush,
+    Pushpop,
+    SlideLeft,
+    SlideRight,
+    RemoveLeft,
+    RemoveRight,
+    /// `Cheap::merge` and `Cheap::merge_with`, whose markers are the bounds `lo`, `md` and `hi`
+    /// of the merge on entry.
+    Merge,
+}
+
+impl CheapOp {
+    /// Every operation, in declaration order.
+    pub const ALL: [CheapOp; 14] = [
+        CheapOp::Recenter,
+        CheapOp::PopLeft,
+        CheapOp::PopRight,
+        CheapOp::PushLeft,
+        CheapOp::PushRight,
+        CheapOp::PushLeftSwap,
+        CheapOp::PushRightSwap,
+        CheapOp::Poppush,
+        CheapOp::Pushpop,
+        CheapOp::SlideLeft,
+        CheapOp::SlideRight,
+        CheapOp::RemoveLeft,
+        CheapOp::RemoveRight,
+        CheapOp::Merge,
+    ];
+
+    /// The operation called `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        CheapOp::ALL.iter().copied().find(|op| op.name() == name)
+    }
+
+    /// The name of the `Cheap` method.
+    pub fn name(self) -> &'static str {
+        match self {
+            CheapOp::Recenter => "recenter",
+            CheapOp::PopLeft => "pop_left",
+            CheapOp::PopRight => "pop_right",
+            CheapOp::PushLeft => "push_left",
+            CheapOp::PushRight => "push_right",
+            CheapOp::PushLeftSwap => "push_left_swap",
+            CheapOp::PushRightSwap => "push_right_swap",
+            CheapOp::Poppush => "poppush",
+            CheapOp::Pushpop => "pushpop",
+            CheapOp::SlideLeft => "slide_left",
+            CheapOp::SlideRight => "slide_right",
+            CheapOp::RemoveLeft => "remove_left",
+            CheapOp::RemoveRight => "remove_right",
+            CheapOp::Merge => "merge",
+        }
+    }
+}
+
+/// Which way a sift carried a value: `Out` from the center towards the leaves, `In` from a leaf
+/// towards the center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sift {
+    Out,
+    In,
+}
+
+/**
+ * Collects statistics about the work done by c-heap operations.
+ *
+ * Every comparison and swap made through a `Cheap` (and the sorts built on it) is reported
+ * here. Use `DummyCounter` when you don't care, and `RealCounter` to tally them.
+ *
+ * Code that moves elements one at a time rather than swapping them, such as a std
+ * `BinaryHeap`, reports each move instead; a swap is worth about three moves.
+ *
+ * The c-heap also reports where its work goes: each public operation is bracketed by `enter`
+ * and `leave`, and each recenter and sift reports its size. These hooks do nothing by default.
+ */
+pub trait Counter {
+    fn count_compare(&mut self);
+    fn count_swap(&mut self);
+    /// Count an element moved on its own rather than swapped. Does nothing by default, since
+    /// the c-heap only swaps.
+    fn count_move(&mut self) {}
+    /// Count a swap of `a[i]` and `a[j]`. Everything that swaps elements of the array reports
+    /// them this way, so a counter can see which elements moved.
+    fn count_swap_at(&mut self, _i: usize, _j: usize) {
+        self.count_swap();
+    }
+    /// Write the collected statistics into a JSON object.
+    fn copy_to(&self, tgt: &mut JsonValue);
+
+    /// Called as a c-heap operation starts, with the markers `(lo, c, hi)` and the index the
+    /// operation was given, if any. Operations nest: `pop_left` may `recenter`, and `merge` calls
+    /// several others.
+    fn enter(&mut self, _op: CheapOp, _params: (usize, usize, usize), _arg: Option<usize>) {}
+    /// Called as the operation `enter` announced finishes, with the markers it left.
+    fn leave(&mut self, _op: CheapOp, _params: (usize, usize, usize)) {}
+    /// Called by `recenter` with the number of elements it's about to sift.
+    fn count_recenter(&mut self, _sifted: usize) {}
+    /// Called after each sift with the number of levels it moved the value.
+    fn count_sift(&mut self, _sift: Sift, _levels: usize) {}
+    /// Called before `a[lo..hi]` is reversed in one go. The reversal isn't counted as swaps.
+    fn count_reverse(&mut self, _lo: usize, _hi: usize) {}
+
+    /// Compare `a` and `b` by `Ord` and count it, as a comparator for `slice::sort_by` and
+    /// friends.
+    fn compare<E: Ord + ?Sized>(&mut self, a: &E, b: &E) -> Ordering
+    where
+        Self: Sized,
+    {
+        self.count_compare();
+        a.cmp(b)
+    }
+}
+
+/// A counter that ignores everything.
+#[derive(Debug, Default)]
+pub struct DummyCounter {}
+
+impl Counter for DummyCounter {
+    fn count_compare(&mut self) {}
+    fn count_swap(&mut self) {}
+    fn copy_to(&self, _tgt: &mut JsonValue) {}
+}
+
+/// Work done inside one kind of c-heap operation.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OpCounts {
+    pub calls: u64,
+    pub compares: u64,
+    pub swaps: u64,
+}
+
+/**
+ * A counter that tallies compares, swaps and moves.
+ *
+ * It also keeps the number of recenters and the elements they sifted, histograms of how many
+ * levels each sift moved, indexed by the number of levels, and the work done in each kind of
+ * operation. Work belongs to the outermost operation running, so the compares and swaps of a
+ * `recenter` inside `pop_left` count toward `pop_left`, and only calls made from outside any
+ * operation are counted as calls. So the operations' counts never overlap, and with the work
+ * outside any operation they add up to the totals.
+ */
+#[derive(Debug, Default)]
+pub struct RealCounter {
+    pub compares: u64,
+    pub swaps: u64,
+    pub moves: u64,
+    pub recenters: u64,
+    pub recenter_sifts: u64,
+    pub sift_out_levels: Vec<u64>,
+    pub sift_in_levels: Vec<u64>,
+    pub ops: [OpCounts; 14],
+    running: Vec<CheapOp>,
+}
+
+impl RealCounter {
+    // The counts for the outermost operation running, if any.
+    #[inline]
+    fn current(&mut self) -> Option<&mut OpCounts> {
+        let op = *self.running.first()?;
+        Some(&mut self.ops[op as usize])
+    }
+}
+
+impl Counter for RealCounter {
+    fn count_compare(&mut self) {
+        self.compares += 1;
+        if let Some(op) = self.current() {
+            op.compares += 1;
+        }
+    }
+    fn count_swap(&mut self) {
+        self.swaps += 1;
+        if let Some(op) = self.current() {
+            op.swaps += 1;
+        }
+    }
+    fn count_move(&mut self) {
+        self.moves += 1;
+    }
+    fn copy_to(&self, tgt: &mut JsonValue) {
+        tgt["compares"] = self.compares.into();
+        tgt["swaps"] = self.swaps.into();
+        tgt["moves"] = self.moves.into();
+        tgt["recenters"] = self.recenters.into();
+        tgt["recenter_sifts"] = self.recenter_sifts.into();
+        if self.recenters > 0 {
+            tgt["sifts_per_recenter"] = (self.recenter_sifts as f64 / self.recenters as f64).into();
+        }
+        tgt["sift_out_levels"] = self.sift_out_levels.clone().into();
+        tgt["sift_in_levels"] = self.sift_in_levels.clone().into();
+
+        let mut ops = JsonValue::new_object();
+        let (mut compares, mut swaps) = (self.compares, self.swaps);
+        for op in CheapOp::ALL.iter() {
+            let counts = &self.ops[*op as usize];
+            if counts.calls > 0 {
+                ops[op.name()] = object! {
+                    "calls" => counts.calls,
+                    "compares" => counts.compares,
+                    "swaps" => counts.swaps,
+                };
+                compares -= counts.compares;
+                swaps -= counts.swaps;
+            }
+        }
+        ops["other"] = object! {
+            "compares" => compares,
+            "swaps" => swaps,
+        };
+        tgt["ops"] = ops;
+    }
+    fn enter(&mut self, op: CheapOp, _params: (usize, usize, usize), _arg: Option<usize>) {
+        if self.running.is_empty() {
+            self.ops[op as usize].calls += 1;
+        }
+        self.running.push(op);
+    }
+    fn leave(&mut self, op: CheapOp, _params: (usize, usize, usize)) {
+        let left = self.running.pop();
+        debug_assert_eq!(left, Some(op), "counter: operations left out of order");
+    }
+    fn count_recenter(&mut self, sifted: usize) {
+        self.recenters += 1;
+        self.recenter_sifts += sifted as u64;
+    }
+    fn count_sift(&mut self, sift: Sift, levels: usize) {
+        let hist = match sift {
+            Sift::Out => &mut self.sift_out_levels,
+            Sift::In => &mut self.sift_in_levels,
+        };
+        if hist.len() <= levels {
+            hist.resize(levels + 1, 0);
+        }
+        hist[levels] += 1;
+    }
+}
@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::fmt;
+
+/**
+ * Why a c-heap operation was refused.
+ *
+ * The `try_` operations on `Cheap` and `RingCheap` check their preconditions up front and leave
+ * the heap untouched when they return one of these.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheapError {
+    /// The operation needs an element, but the c-heap is empty.
+    Empty,
+    /// The operation would move the c-heap, or reach an index, past the ends of the array.
+    OutOfBounds,
+    /// The index to push is already inside the c-heap.
+    IndexInsideHeap,
+    /// The index to remove is not inside the c-heap.
+    IndexOutsideHeap,
+    /// The ring has no free slot to push or slide into.
+    Full,
+}
+
+impl fmt::Display for CheapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheapError::Empty => "c-heap error: pop when empty",
+            CheapError::OutOfBounds => "c-heap error: attempt to pass array boundary",
+            CheapError::IndexInsideHeap => {
+                "c-heap error: attempted to push an index already inside c-heap"
+            }
+            CheapError::IndexOutsideHeap => {
+                "c-heap error: attempted to remove an index outside c-heap"
+            }
+            CheapError::Full => "c-heap error: attempt to push or slide a full ring",
+        })
+    }
+}
+
+impl Error for CheapError {}
+
+/**
+ * Why `replay` couldn't reproduce a trace.
+ *
+ * Lines are numbered from 1, counting every line of the trace.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The line isn't an event `replay` understands, or it points outside the array.
+    Malformed { line: usize, text: String },
+    /// Replaying produced `got` where the trace recorded `expected`, or the array doesn't hold
+    /// what the recorded call `expected` needs to start, as `got` says.
+    Mismatch {
+        line: usize,
+        expected: String,
+        got: String,
+    },
+    /// The trace ended in the middle of an operation, where replaying produced `got`.
+    Truncated { got: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Malformed { line, text } => {
+                write!(f, "replay error: line {} is malformed: {}", line, text)
+            }
+            ReplayError::Mismatch {
+                line,
+                expected,
+                got,
+            } => write!(
+                f,
+                "replay error: line {} expected {} but got {}",
+                line, expected, got
+            ),
+            ReplayError::Truncated { got } => {
+                write!(
+                    f,
+                    "replay error: trace ended, but replaying went on to {}",
+                    got
+                )
+            }
+        }
+    }
+}
+
+impl Error for ReplayError {}
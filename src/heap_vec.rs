@@ -0,0 +1,208 @@
+use crate::cheap::Cheap;
+use crate::counter::{Counter, DummyCounter};
+use crate::order::{NaturalOrder, Order};
+use std::fmt;
+
+// Smallest buffer allocated once the container holds anything.
+const MIN_CAPACITY: usize = 8;
+
+/*
+ * Ranks occupied slots by their contents. The c-heap never looks outside lo..hi, and every slot
+ * in there is occupied.
+ */
+struct SlotOrder<'o, O>(&'o mut O);
+
+impl<'o, T, O: Order<T>> Order<Option<T>> for SlotOrder<'o, O> {
+    #[inline]
+    fn le(&mut self, a: &Option<T>, b: &Option<T>) -> bool {
+        self.0.le(slot(a), slot(b))
+    }
+    #[inline]
+    fn lt(&mut self, a: &Option<T>, b: &Option<T>) -> bool {
+        self.0.lt(slot(a), slot(b))
+    }
+}
+
+#[inline]
+fn slot<T>(v: &Option<T>) -> &T {
+    v.as_ref().expect("c-heap state: empty slot inside c-heap")
+}
+
+/**
+ * An owned priority queue built on a centered heap.
+ *
+ * Elements live in a buffer with slack on both sides of the heap, so they can be pushed from
+ * either end like a `VecDeque`. Pops always return the best element; `pop_front` and `pop_back`
+ * only differ in which end of the heap gives up its slot.
+ *
+ * When a push finds no room on its side, the live range is moved back to the middle of the
+ * buffer, which doubles in size first if it's more than half full. Moving the range doesn't
+ * disturb the heap, since a c-heap's shape only depends on distances from the center.
+ */
+#[derive(Debug)]
+pub struct CenteredHeapVec<T: fmt::Debug, O = NaturalOrder, C: Counter + fmt::Debug = DummyCounter>
+{
+    buf: Vec<Option<T>>,
+    lo: usize,
+    c: usize,
+    hi: usize,
+    ord: O,
+    cnt: C,
+}
+
+impl<T: PartialOrd + fmt::Debug> CenteredHeapVec<T> {
+    /// Construct an empty container ordered by `PartialOrd`.
+    pub fn new() -> Self {
+        CenteredHeapVec::with_parts(0, NaturalOrder, DummyCounter {})
+    }
+
+    /// Construct an empty container with room for `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        CenteredHeapVec::with_parts(capacity, NaturalOrder, DummyCounter {})
+    }
+}
+
+impl<T: PartialOrd + fmt::Debug> Default for CenteredHeapVec<T> {
+    fn default() -> Self {
+        CenteredHeapVec::new()
+    }
+}
+
+impl<T: fmt::Debug, O: Order<T>, C: Counter + fmt::Debug> CenteredHeapVec<T, O, C> {
+    /// Construct an empty container with room for `capacity` elements, ranked by `ord` and
+    /// reporting to `cnt`.
+    pub fn with_parts(capacity: usize, ord: O, cnt: C) -> Self {
+        let mut buf = Vec::with_capacity(capacity);
+        buf.resize_with(capacity, || None);
+        let mid = capacity / 2;
+        CenteredHeapVec {
+            buf,
+            lo: mid,
+            c: mid,
+            hi: mid,
+            ord,
+            cnt,
+        }
+    }
+
+    /// Number of elements held.
+    pub fn len(&self) -> usize {
+        self.hi - self.lo
+    }
+
+    /// Whether no elements are held.
+    pub fn is_empty(&self) -> bool {
+        self.lo == self.hi
+    }
+
+    /// Number of slots in the buffer, including the slack on both sides.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// The best element, if any.
+    pub fn peek(&self) -> Option<&T> {
+        if self.is_empty() {
+            None
+        } else {
+            self.buf[self.c].as_ref()
+        }
+    }
+
+    /// The counter that has seen every compare and swap.
+    pub fn counter(&self) -> &C {
+        &self.cnt
+    }
+
+    /// Add an element through the low end of the heap.
+    pub fn push_front(&mut self, v: T) {
+        if self.lo == 0 {
+            self.make_room();
+        }
+        self.buf[self.lo - 1] = Some(v);
+        self.with_cheap(|ch| ch.push_left());
+    }
+
+    /// Add an element through the high end of the heap.
+    pub fn push_back(&mut self, v: T) {
+        if self.hi == self.buf.len() {
+            self.make_room();
+        }
+        self.buf[self.hi] = Some(v);
+        self.with_cheap(|ch| ch.push_right());
+    }
+
+    /// Remove the best element, giving up the slot at the low end of the heap.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let lo = self.lo;
+        self.with_cheap(|ch| ch.pop_left());
+        self.buf[lo].take()
+    }
+
+    /// Remove the best element, giving up the slot at the high end of the heap.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let hi = self.hi;
+        self.with_cheap(|ch| ch.pop_right());
+        self.buf[hi - 1].take()
+    }
+
+    /// Drop every element, keeping the buffer.
+    pub fn clear(&mut self) {
+        for v in &mut self.buf[self.lo..self.hi] {
+            *v = None;
+        }
+        let mid = self.buf.len() / 2;
+        self.lo = mid;
+        self.c = mid;
+        self.hi = mid;
+    }
+
+    // Run a c-heap operation over the live range and save the markers it leaves behind.
+    fn with_cheap<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Cheap<Option<T>, C, SlotOrder<O>>),
+    {
+        let mut ch = Cheap::new_range(&mut self.buf, self.lo, self.c, self.hi, &mut self.cnt)
+            .with_order(SlotOrder(&mut self.ord));
+        f(&mut ch);
+        let (lo, c, hi) = ch.params();
+        self.lo = lo;
+        self.c = c;
+        self.hi = hi;
+    }
+
+    /*
+     * Move the live range to the middle of the buffer, doubling the buffer first if it's more
+     * than half full. Afterwards both ends have at least one free slot.
+     */
+    fn make_room(&mut self) {
+        let len = self.len();
+        let mut cap = self.buf.len();
+        if (len + 1) * 2 > cap {
+            cap = (cap * 2).max(MIN_CAPACITY);
+            self.buf.resize_with(cap, || None);
+        }
+        let lo = (cap - len) / 2;
+        dbg_println!(
+            "heap_vec: moving lo={}, hi={} to lo={}, capacity={}",
+            self.lo,
+            self.hi,
+            lo,
+            cap
+        );
+        if lo < self.lo {
+            self.buf[lo..self.hi].rotate_left(self.lo - lo);
+        } else {
+            self.buf[self.lo..lo + len].rotate_right(lo - self.lo);
+        }
+        self.c = self.c + lo - self.lo;
+        self.lo = lo;
+        self.hi = lo + len;
+    }
+}
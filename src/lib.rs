@@ -0,0 +1,111 @@
+/*!
+ * The centered heap.
+ *
+ * A centered heap (c-heap) is a binary heap stored in a slice whose root sits at a `center`
+ * index between `lo` and `hi`. Children may be found on both sides of the center, so the heap
+ * can grow and shrink from either end and even slide across the slice.
+ *
+ * ```text
+ *     [  other data  |      heap         |    other data     ]
+ *                 lo^        ^center    ^hi
+ * ```
+ *
+ * The main type is [`Cheap`], which borrows a slice and a [`Counter`]. On top of it are the
+ * sorting entry points: [`merge_sort`] (using [`Cheap::merge`] for in-place merges),
+ * [`heap_sort_left`], [`heap_sort_right`], [`running_sort_left`] and [`running_sort_right`].
+ *
+ * The c-heap operations panic when their preconditions fail, such as popping an empty heap or
+ * pushing past the end of the array. Each has a `try_` variant that returns a [`CheapError`]
+ * instead.
+ *
+ * [`RingCheap`] treats its slice as a ring, so the heap can slide past the end of the slice and
+ * keep going; [`running_sort_stream`] uses it to sort an unbounded stream within a window.
+ *
+ * [`CenteredHeapVec`] wraps a c-heap in an owned, growable buffer for use as a priority queue.
+ *
+ * The c-heap merge is not stable. [`stable_merge`] and [`stable_merge_sort`] keep equal elements
+ * in their original order, but they are not in place: each call allocates one word of scratch
+ * space per element.
+ *
+ * Elements are ranked by an [`Order`]. The plain entry points use `PartialOrd`; each has a
+ * `_with` variant taking any `Order`, a `_by` variant taking a comparator and a `_by_key` variant
+ * taking a key function, mirroring `slice::sort_by` and `slice::sort_by_key`.
+ *
+ * `PartialOrd` leaves NaN unordered, which breaks the heap invariant. Sort floats with
+ * [`TotalOrder`] or wrap them in [`TotalOrd`], both of which put NaNs last.
+ *
+ * A [`Counter`] sees every compare and swap, and each c-heap operation tells it when it starts
+ * and finishes ([`CheapOp`]) and how far its recenters and sifts ([`Sift`]) went, so
+ * [`RealCounter`] can break the work down per operation. [`Tracer`] writes every operation and
+ * swap down as JSON lines instead, and [`replay`] checks a trace still plays out the same way.
+ *
+ * Debug builds run the full invariant checks after every operation and log a great deal to
+ * stderr; release builds do neither.
+ */
+
+macro_rules! dbg_println {
+    ($($arg:expr), *) => {
+        #[cfg(debug_assertions)]
+        eprintln!($($arg), *);
+    };
+}
+
+macro_rules! dbg_show_call {
+    ($self:ident, $($method:expr), *) => {
+        #[cfg(debug_assertions)]
+        show_call!($self, $($method), *);
+    };
+}
+
+macro_rules! show_call {
+    ($self:ident, $($method:expr), *) => {
+        let (lo, c, hi) = $self.params();
+        eprint!($($method), *);
+        eprintln!("lo={}, c={}, hi={}) {:?}", lo, c, hi, $self);
+    }
+}
+
+mod cheap;
+mod counter;
+mod error;
+mod heap_vec;
+mod median;
+mod merge;
+mod order;
+mod ring;
+mod sort;
+mod stable;
+mod trace;
+mod window;
+
+pub use crate::cheap::{CenteredHeap, Cheap, DrainLeft, DrainRight, IntoSortedIter};
+pub use crate::counter::{CheapOp, Counter, DummyCounter, OpCounts, RealCounter, Sift};
+pub use crate::error::{CheapError, ReplayError};
+pub use crate::heap_vec::CenteredHeapVec;
+pub use crate::median::SlidingMedian;
+pub use crate::merge::{
+    block_swap_merge, block_swap_merge_with, buffered_merge, buffered_merge_with, merge_runs,
+    merge_runs_by, merge_runs_by_key, merge_runs_with, sym_merge, sym_merge_with, MergeStrategy,
+};
+pub use crate::order::{
+    ByKey, ByOrdering, NaturalOrder, Order, Reversed, TotalCmp, TotalOrd, TotalOrder,
+};
+pub use crate::ring::{running_sort_stream, RingCheap};
+pub use crate::sort::{
+    bottom_up_merge_sort, bottom_up_merge_sort_by, bottom_up_merge_sort_by_key,
+    bottom_up_merge_sort_with, heap_sort_left, heap_sort_left_by, heap_sort_left_by_key,
+    heap_sort_left_with, heap_sort_right, heap_sort_right_by, heap_sort_right_by_key,
+    heap_sort_right_with, is_partially_sorted, is_selected, is_sorted, is_sorted_with, merge_sort,
+    merge_sort_by, merge_sort_by_key, merge_sort_with, natural_merge_sort, natural_merge_sort_by,
+    natural_merge_sort_by_key, natural_merge_sort_with, partial_sort, partial_sort_by,
+    partial_sort_by_key, partial_sort_with, running_sort_left, running_sort_left_by,
+    running_sort_left_by_key, running_sort_left_with, running_sort_right, running_sort_right_by,
+    running_sort_right_by_key, running_sort_right_with, select_nth, select_nth_by,
+    select_nth_by_key, select_nth_with, select_top_k, small_sort, small_sort_with,
+};
+pub use crate::stable::{
+    stable_merge, stable_merge_by, stable_merge_by_key, stable_merge_sort, stable_merge_sort_by,
+    stable_merge_sort_by_key,
+};
+pub use crate::trace::{replay, ReplayStats, Tracer};
+pub use crate::window::SlidingWindow;
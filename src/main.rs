@@ -4,1164 +4,1544 @@ extern crate enum_display_derive;
 #[macro_use]
 extern crate json;
 
-use clap::{App, Arg};
+use cheap::{
+    bottom_up_merge_sort, heap_sort_left, heap_sort_right, is_partially_sorted, is_selected,
+    is_sorted, merge_runs, merge_sort, natural_merge_sort, partial_sort, replay, running_sort_left,
+    running_sort_right, select_nth, Counter, DummyCounter, MergeStrategy, RealCounter,
+    SlidingWindow, TotalOrd, Tracer,
+};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use fmt::Display;
 use json::JsonValue;
-use rand::prelude::{thread_rng, Rng, SliceRandom};
+use rand::prelude::{thread_rng, Rng, SeedableRng, SliceRandom, StdRng};
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 use std::fmt;
-use std::io;
-
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::iter;
+use std::process;
 use std::time::SystemTime;
 
 /*
- * The left child in a centered heap is either center - 1, or twice the distance from center.
- * Uses usize::MAX for known out of bounds as we expect that will fail a bounds check.
+ * Settings from the command line that only some operations use.
  */
-#[inline]
-fn get_left_child(x: usize, c: usize) -> usize {
-    if x == c {
-        if c == 0 {
-            usize::MAX
-        } else {
-            c - 1
-        }
-    } else if x > c {
-        c + (x - c) * 2
-    } else {
-        let o = (c - x) * 2;
-        if o > c {
-            usize::MAX
-        } else {
-            c - o
+struct Params {
+    run_size: usize,
+    k: usize,
+    nth: Option<usize>,
+    block: Option<usize>,
+    merge: MergeStrategy,
+}
+
+#[derive(Display)]
+enum Op {
+    MergeSort,
+    NaturalMergeSort,
+    BottomUpMergeSort,
+    HeapSortLeft,
+    HeapSortRight,
+    RunningSortLeft,
+    RunningSortRight,
+    TopK,
+    SelectNth,
+    WindowMin,
+    WindowMax,
+    MergeRuns,
+    Sort,
+    SortUnstable,
+    BinaryHeapSort,
+    Unknown,
+}
+
+impl Op {
+    fn from_str(op: Option<&str>) -> Self {
+        match op {
+            Some(op_str) => match op_str {
+                "merge" => Op::MergeSort,
+                "natural_merge" => Op::NaturalMergeSort,
+                "bottom_up" => Op::BottomUpMergeSort,
+                "heap_left" => Op::HeapSortLeft,
+                "heap_right" => Op::HeapSortRight,
+                "run_left" => Op::RunningSortLeft,
+                "run_right" => Op::RunningSortRight,
+                "top_k" => Op::TopK,
+                "select_nth" => Op::SelectNth,
+                "window_min" => Op::WindowMin,
+                "window_max" => Op::WindowMax,
+                "merge_runs" => Op::MergeRuns,
+                "sort" => Op::Sort,
+                "sort_unstable" => Op::SortUnstable,
+                "binary_heap" => Op::BinaryHeapSort,
+                _ => Op::Unknown,
+            },
+            None => Op::Unknown,
         }
     }
-}
 
-/*
- * The right child in a centered heap is either center + 1, or twice the distance from center
- * plus one.
- * Uses usize::MAX for known out of bounds as we expect that will fail a bounds check.
- */
-#[inline]
-fn get_right_child(x: usize, c: usize) -> usize {
-    if x == c {
-        c + 1
-    } else if x > c {
-        (x - c) * 2 + 1 + c
-    } else {
-        let o = (c - x) * 2 + 1;
-        if o > c {
-            usize::MAX
-        } else {
-            c - o
+    fn does_sort(&self) -> bool {
+        matches!(
+            self,
+            Op::Sort
+                | Op::SortUnstable
+                | Op::BinaryHeapSort
+                | Op::MergeSort
+                | Op::NaturalMergeSort
+                | Op::BottomUpMergeSort
+                | Op::HeapSortLeft
+                | Op::HeapSortRight
+                | Op::MergeRuns
+        )
+    }
+
+    fn is_window(&self) -> bool {
+        matches!(self, Op::WindowMin | Op::WindowMax)
+    }
+
+    /*
+     * Whether every c-heap the operation builds ranks the array itself in natural order, which
+     * is what `replay` assumes.
+     */
+    fn is_traceable(&self) -> bool {
+        matches!(
+            self,
+            Op::MergeSort
+                | Op::NaturalMergeSort
+                | Op::BottomUpMergeSort
+                | Op::HeapSortLeft
+                | Op::HeapSortRight
+                | Op::RunningSortLeft
+                | Op::RunningSortRight
+                | Op::TopK
+        )
+    }
+
+    fn run<C: Counter + fmt::Debug, E: Ord + fmt::Debug>(
+        &self,
+        n: &mut Vec<E>,
+        p: &Params,
+        cnt: &mut C,
+    ) {
+        let n_len = n.len();
+        let (run_size, k, nth) = (p.run_size, p.k, p.nth.unwrap_or(n_len / 2));
+        match self {
+            Op::MergeSort => merge_sort(n, 0, n_len, p.merge.merge_fn(), cnt),
+            Op::NaturalMergeSort => natural_merge_sort(n, 0, n_len, cnt),
+            Op::BottomUpMergeSort => {
+                bottom_up_merge_sort(n, 0, n_len, p.block, p.merge.merge_fn(), cnt)
+            }
+            Op::HeapSortLeft => heap_sort_left(n, cnt),
+            Op::HeapSortRight => heap_sort_right(n, cnt),
+            Op::Sort => n.sort_by(|a, b| cnt.compare(a, b)),
+            Op::SortUnstable => n.sort_unstable_by(|a, b| cnt.compare(a, b)),
+            Op::BinaryHeapSort => binary_heap_sort(n, cnt),
+            Op::RunningSortLeft => running_sort_left(n, run_size, cnt),
+            Op::RunningSortRight => running_sort_right(n, run_size, cnt),
+            Op::TopK => partial_sort(n, k, cnt),
+            Op::SelectNth => {
+                select_nth(n, nth, cnt);
+            }
+            Op::WindowMin => {
+                SlidingWindow::min(n, run_size, cnt).for_each(drop);
+            }
+            Op::WindowMax => {
+                SlidingWindow::max(n, run_size, cnt).for_each(drop);
+            }
+            Op::MergeRuns => merge_runs(n, &run_bounds(n_len, k), cnt),
+            Op::Unknown => usage("Unknown operation"),
         }
     }
 }
 
 /*
- * The parent node is half the distance from the center, rounded down.
+ * Settings from the command line that shape the test array. Each generator uses at most one.
  */
-#[inline]
-fn get_parent(x: usize, c: usize) -> usize {
-    debug_assert!(x != c, "cheap-state: can't find parent of center node");
-    if x > c {
-        c + (x - c) / 2
-    } else {
-        c - (c - x) / 2
-    }
+struct ArrayParams {
+    size: usize,
+    seed: u64,
+    str_len: usize,
+    period: usize,
+    swaps: usize,
+    distinct: usize,
+    zipf: f64,
+    run_len: usize,
 }
 
 /*
- * The recenter limit identifies where nodes must be sifted in order to fully recenter.
- * This is half the distance from the center, rounded up.
+ * An element of a std `BinaryHeap` that counts its compares.
  */
-#[inline]
-fn get_recenter_limit(x: usize, c: usize) -> usize {
-    if x > c {
-        (x - c + 1) / 2 + c
-    } else {
-        c - (c - x + 1) / 2
-    }
+struct Counted<'r, 'c, E, C> {
+    e: E,
+    cnt: &'r RefCell<&'c mut C>,
 }
 
-macro_rules! dbg_println {
-    ($($arg:expr), *) => {
-        #[cfg(debug_assertions)]
-        eprintln!($($arg), *);
-    };
+impl<'r, 'c, E: Ord, C: Counter> PartialEq for Counted<'r, 'c, E, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
 }
 
-macro_rules! dbg_show_call {
-    ($self:ident, $($method:expr), *) => {
-        #[cfg(debug_assertions)]
-        show_call!($self, $($method), *);
-    };
-}
+impl<'r, 'c, E: Ord, C: Counter> Eq for Counted<'r, 'c, E, C> {}
 
-macro_rules! show_call {
-    ($self:ident, $($method:expr), *) => {
-        eprint!($($method), *);
-        eprintln!("lo={}, c={}, hi={}) {:?}", $self.lo, $self.c, $self.hi, $self);
+impl<'r, 'c, E: Ord, C: Counter> PartialOrd for Counted<'r, 'c, E, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-trait Counter {
-    fn count_compare(&mut self);
-    fn count_swap(&mut self);
-    fn copy_to(&self, tgt: &mut JsonValue);
+impl<'r, 'c, E: Ord, C: Counter> Ord for Counted<'r, 'c, E, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cnt.borrow_mut().compare(&self.e, &other.e)
+    }
 }
 
-#[derive(Debug)]
-struct DummyCounter {}
-
-impl Counter for DummyCounter {
-    fn count_compare(&mut self) {}
-    fn count_swap(&mut self) {}
-    fn copy_to(&self, _tgt: &mut JsonValue) {}
+/*
+ * Heap sort with a std `BinaryHeap` as a baseline. Elements are moved into the heap and back
+ * out in order, and those moves are counted, but the heap moves elements internally where they
+ * can't be seen. The heap is a min-heap, so the elements come out in the order they go back.
+ */
+fn binary_heap_sort<E: Ord, C: Counter>(n: &mut Vec<E>, cnt: &mut C) {
+    let cnt = RefCell::new(cnt);
+    let mut heap: BinaryHeap<Reverse<Counted<E, C>>> = n
+        .drain(..)
+        .map(|e| {
+            cnt.borrow_mut().count_move();
+            Reverse(Counted { e, cnt: &cnt })
+        })
+        .collect();
+    while let Some(Reverse(top)) = heap.pop() {
+        n.push(top.e);
+        cnt.borrow_mut().count_move();
+    }
 }
 
-#[derive(Debug)]
-struct RealCounter {
-    compares: u64,
-    swaps: u64,
+#[derive(Display)]
+enum ArrayCon {
+    Shuffle,
+    Random,
+    Count,
+    Reverse,
+    OrganPipe,
+    Sawtooth,
+    NearlySorted,
+    FewUnique,
+    Zipf,
+    MedianOf3Killer,
+    SortedRuns,
+    Input,
+    Unknown,
 }
 
-impl Counter for RealCounter {
-    fn count_compare(&mut self) {
-        self.compares += 1;
+impl ArrayCon {
+    fn from_str(ac: Option<&str>) -> Self {
+        match ac {
+            Some(ac_str) => match ac_str {
+                "shuffle" => ArrayCon::Shuffle,
+                "random" => ArrayCon::Random,
+                "count" => ArrayCon::Count,
+                "reverse" => ArrayCon::Reverse,
+                "organ_pipe" => ArrayCon::OrganPipe,
+                "sawtooth" => ArrayCon::Sawtooth,
+                "nearly_sorted" => ArrayCon::NearlySorted,
+                "few_unique" => ArrayCon::FewUnique,
+                "zipf" => ArrayCon::Zipf,
+                "median3_killer" => ArrayCon::MedianOf3Killer,
+                "sorted_runs" => ArrayCon::SortedRuns,
+                _ => ArrayCon::Unknown,
+            },
+            None => ArrayCon::Unknown,
+        }
+    }
+
+    /*
+     * Construct an array based on a string integer provided on the command line. The array is
+     * built from ranks, then each rank is replaced by the element of that rank, so every element
+     * type gets an array of the same shape.
+     */
+    fn make_array<E: Element>(&self, ap: &ArrayParams) -> Vec<E> {
+        let mut rng = StdRng::seed_from_u64(ap.seed);
+        let a = self.make_ranks(ap.size, ap, &mut rng);
+        let ranked = E::ascending(ap.size, ap.str_len, &mut rng);
+        a.into_iter().map(|r| ranked[r].clone()).collect()
     }
-    fn count_swap(&mut self) {
-        self.swaps += 1;
+
+    /*
+     * The ranks of the elements of the test array, each less than `num_elems`.
+     */
+    fn make_ranks<R: Rng>(&self, num_elems: usize, ap: &ArrayParams, rng: &mut R) -> Vec<usize> {
+        let n = num_elems;
+        let mut a: Vec<usize> = match self {
+            ArrayCon::Random => (0..n).map(|_| rng.gen_range(0..n)).collect(),
+            ArrayCon::OrganPipe => (0..n).map(|i| i.min(n - 1 - i)).collect(),
+            ArrayCon::Sawtooth => (0..n).map(|i| i % ap.period.max(1)).collect(),
+            ArrayCon::FewUnique => {
+                let m = ap.distinct.clamp(1, n.max(1));
+                (0..n).map(|_| rng.gen_range(0..m) * n / m).collect()
+            }
+            ArrayCon::Zipf => zipf_ranks(n, ap.zipf, rng),
+            ArrayCon::MedianOf3Killer => median_of_3_killer(n),
+            ArrayCon::Input | ArrayCon::Unknown => Vec::new(),
+            _ => (0..n).collect(),
+        };
+        match self {
+            ArrayCon::Shuffle => {
+                a.shuffle(rng);
+            }
+            ArrayCon::Reverse => {
+                a.reverse();
+            }
+            ArrayCon::NearlySorted if n > 1 => {
+                for _ in 0..ap.swaps {
+                    a.swap(rng.gen_range(0..n), rng.gen_range(0..n));
+                }
+            }
+            ArrayCon::SortedRuns => {
+                a.shuffle(rng);
+                for run in a.chunks_mut(ap.run_len.max(1)) {
+                    run.sort_unstable();
+                }
+            }
+            _ => (),
+        }
+        a
     }
-    fn copy_to(&self, tgt: &mut JsonValue) {
-        tgt["compares"] = self.compares.into();
-        tgt["swaps"] = self.swaps.into();
+
+    /*
+     * Record the seed and the setting that shaped the array, if it used one.
+     */
+    fn report(&self, ap: &ArrayParams, out: &mut JsonValue) {
+        if let ArrayCon::Input = self {
+            return;
+        }
+        out["seed"] = ap.seed.into();
+        match self {
+            ArrayCon::Sawtooth => out["period"] = ap.period.into(),
+            ArrayCon::NearlySorted => out["array_swaps"] = ap.swaps.into(),
+            ArrayCon::FewUnique => out["distinct"] = ap.distinct.into(),
+            ArrayCon::Zipf => out["zipf"] = ap.zipf.into(),
+            ArrayCon::SortedRuns => out["run_len"] = ap.run_len.into(),
+            _ => (),
+        }
     }
 }
 
-struct Cheap<'a, E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug> {
-    a: &'a mut [E],
-    lo: usize,
-    c: usize,
-    hi: usize,
-    cnt: &'a mut C,
+/*
+ * Ranks drawn so that rank `i` turns up in proportion to 1 / (i + 1)^`s`.
+ */
+fn zipf_ranks<R: Rng>(n: usize, s: f64, rng: &mut R) -> Vec<usize> {
+    let mut total = 0.0;
+    let cdf: Vec<f64> = (0..n)
+        .map(|i| {
+            total += 1.0 / ((i + 1) as f64).powf(s);
+            total
+        })
+        .collect();
+    (0..n)
+        .map(|_| {
+            let u = rng.gen_range(0.0..total);
+            cdf.partition_point(|&c| c <= u).min(n - 1)
+        })
+        .collect()
 }
 
-impl<'a, E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug> Cheap<'a, E, C> {
-    // Construct a c-heap oriented at the left end of the array.
-    fn new_left(a: &'a mut [E], cnt: &'a mut C) -> Self {
-        Cheap {
-            a: a,
-            lo: 0,
-            c: 0,
-            hi: 0,
-            cnt: cnt,
+/*
+ * Musser's sequence that drives a median-of-3 quicksort to quadratic time. It's built for the
+ * largest multiple of 4 that fits, and the rest counts up at the end.
+ */
+fn median_of_3_killer(n: usize) -> Vec<usize> {
+    let k = n / 4 * 2;
+    let mut a: Vec<usize> = (0..n).collect();
+    for i in 1..=k {
+        if i % 2 == 1 {
+            a[i - 1] = i - 1;
+            a[i] = k + i - 1;
         }
+        a[k + i - 1] = 2 * i - 1;
     }
+    a
+}
 
-    // Construct a c-heap oriented at the right end of the array.
-    fn new_right(a: &'a mut [E], cnt: &'a mut C) -> Self {
-        let i = a.len();
-        Cheap {
-            a: a,
-            lo: i,
-            c: i,
-            hi: i,
-            cnt: cnt,
-        }
+/*
+ * A type the test array can be made of.
+ */
+trait Element: Ord + Clone + fmt::Debug {
+    /*
+     * The name `--type` knows the element by.
+     */
+    const NAME: &'static str;
+
+    /*
+     * `n` elements in ascending order; the array takes the element at each rank.
+     */
+    fn ascending<R: Rng>(n: usize, str_len: usize, rng: &mut R) -> Vec<Self>;
+
+    /*
+     * Read an element from a whitespace separated token or a JSON array member.
+     */
+    fn parse(token: &str) -> Option<Self>;
+    fn from_json(v: &JsonValue) -> Option<Self>;
+
+    /*
+     * Write the element as `parse` reads it.
+     */
+    fn to_text(&self) -> String;
+}
+
+impl Element for i32 {
+    const NAME: &'static str = "i32";
+
+    fn ascending<R: Rng>(n: usize, _: usize, _: &mut R) -> Vec<Self> {
+        (0..n as i32).collect()
     }
 
-    // Construct a c-heap spanning the whole array, centered at the left.
-    fn new_spanleft(a: &'a mut [E], cnt: &'a mut C) -> Self {
-        let i = a.len();
-        Cheap {
-            a: a,
-            lo: 0,
-            c: 0,
-            hi: i,
-            cnt: cnt,
-        }
+    fn parse(token: &str) -> Option<Self> {
+        token.parse().ok()
     }
 
-    // Construct a c-heap spanning the whole array, centered at the left.
-    #[allow(dead_code)]
-    fn new_spanright(a: &'a mut [E], cnt: &'a mut C) -> Self {
-        let i = a.len();
-        Cheap {
-            a: a,
-            lo: 0,
-            c: i - 1,
-            hi: i,
-            cnt: cnt,
-        }
+    fn from_json(v: &JsonValue) -> Option<Self> {
+        v.as_i32()
     }
 
-    // Get the parameters as isizes for use in calculations.
-    #[inline]
-    fn params(&self) -> (usize, usize, usize) {
-        (self.lo, self.c, self.hi)
+    fn to_text(&self) -> String {
+        self.to_string()
     }
+}
 
-    #[inline]
-    fn swap(&mut self, i: usize, j: usize) {
-        self.cnt.count_swap();
-        self.a.swap(i, j);
+impl Element for u64 {
+    const NAME: &'static str = "u64";
+
+    fn ascending<R: Rng>(n: usize, _: usize, _: &mut R) -> Vec<Self> {
+        (0..n as u64).collect()
     }
 
-    // Check if a[i] is "better than" a[j].
-    #[inline]
-    fn bt(&mut self, i: usize, j: usize) -> bool {
-        self.cnt.count_compare();
-        return self.a[i] <= self.a[j];
+    fn parse(token: &str) -> Option<Self> {
+        token.parse().ok()
     }
 
-    // Check if a[i] is "better than" a[j].
-    #[inline]
-    fn bt_nocount(&self, i: usize, j: usize) -> bool {
-        return self.a[i] <= self.a[j];
+    fn from_json(v: &JsonValue) -> Option<Self> {
+        v.as_u64()
     }
 
-    // Check only the range invariants.
-    #[allow(dead_code)]
-    fn check_range(&self) {
-        let (lo, c, hi) = self.params();
-        assert!(
-            /* 0 <= lo && */ hi <= self.a.len(),
-            "c-heap state: markers outside array"
-        );
-        assert!(
-            lo == c && c == hi || lo <= c && c <= hi,
-            "c-heap state: markers invalid"
-        );
-    }
-
-    // Do a full check of the invariants.
-    #[allow(dead_code)]
-    fn check(&self) {
-        self.check_range();
-        assert!(self.is_valid(), "c-heap state: heap invariant failed");
+    fn to_text(&self) -> String {
+        self.to_string()
     }
+}
 
-    #[allow(dead_code)]
-    fn is_valid(&self) -> bool {
-        let (lo, c, hi) = self.params();
-        for i in lo..hi {
-            if i != c {
-                let p = get_parent(i, c);
-                let x = self.bt_nocount(p, i);
-                if !x {
-                    show_call!(
-                        self,
-                        "check: failed(a[{}]={:?} bt a[{}]={:?}, ",
-                        p,
-                        &self.a[p],
-                        i,
-                        &self.a[i]
-                    );
-                    return false;
-                }
-            }
-        }
-        return true;
-    }
-
-    fn is_empty(&self) -> bool {
-        debug_assert!(self.lo <= self.hi, "c-heap state error: markers invalid");
-        self.lo == self.hi
-    }
-
-    /**
-     * Recentering initializes the heap at a range from lo to (but not including) hi
-     * and with a center at c.
-     *
-     * After running this, a valid center heap exists as follows:
-     *
-     *     . . . [x, x, x, C, x, x, x] . . .
-     *          lo^                    ^hi
-     *
-     * The best value (per bt) will be located at index c.
-     *
-     * Data outside the range [lo:hi] will be unaffected.
-     */
-    fn recenter(&mut self) {
-        dbg_show_call!(self, "recenter-start(");
-        #[cfg(debug_assertions)]
-        self.check_range();
-        let (lo, c, hi) = self.params();
-        for i in get_recenter_limit(lo, c)..c {
-            self.sift_out(i);
-        }
-        for i in (c..get_recenter_limit(hi, c)).rev() {
-            self.sift_out(i);
-        }
-        #[cfg(debug_assertions)]
-        self.check();
-        dbg_show_call!(self, "recenter-end(");
-    }
-
-    /**
-     * Move a root node towards a leaf.
-     *
-     *   leaf   leaf
-     *       node
-     *
-     * We inspect each leaf, and if the leaf is "better than" the node, we'll swap them to preserve
-     * the invariant.
-     */
-    fn sift_out(&mut self, ii: usize) {
-        dbg_show_call!(self, "sift_out-start({}, ", ii);
-        let mut vio = 0;
-        let mut vio_found = true;
-        let mut n = ii;
-        let (lo, c, hi) = self.params();
-
-        while vio_found {
-            vio_found = false;
-
-            let ch1 = get_left_child(n, c);
-            if lo <= ch1 && ch1 < hi {
-                if self.bt(ch1, n) {
-                    vio = ch1;
-                    vio_found = true;
-                    dbg_println!("sift: left child {} is better than parent {}", ch1, n);
-                } else {
-                    dbg_println!("sift: parent {} is better than left child {}", n, ch1);
-                }
-            } else {
-                dbg_println!("sift: left child {} is out of range", ch1);
-            }
+/*
+ * Floats ordered by `TotalCmp`, so NaNs sort last. The generated arrays run from -SIZE / 8 to
+ * SIZE / 8 in steps of 1/4.
+ */
+impl Element for TotalOrd<f64> {
+    const NAME: &'static str = "f64";
 
-            let ch2 = get_right_child(n, c);
-            if lo <= ch2 && ch2 < hi {
-                if self.bt(ch2, n) {
-                    dbg_println!("sift: right child {} is better than parent {}", ch2, n);
-                    if !vio_found || self.bt(ch2, vio) {
-                        dbg_println!("sift: and right child is better than left child");
-                        vio_found = true;
-                        vio = ch2;
-                    }
-                } else {
-                    dbg_println!("sift: parent {} is better than right child {}", n, ch2);
-                }
-            } else {
-                dbg_println!("sift: right child {} is out of range", ch2);
-            }
-            if vio_found {
-                dbg_println!("sift: swap {} and {}", n, vio);
-                self.swap(n, vio);
-                n = vio;
-            }
-        }
-        dbg_show_call!(self, "sift_out-end({}, ", ii);
+    fn ascending<R: Rng>(n: usize, _: usize, _: &mut R) -> Vec<Self> {
+        let mid = (n / 2) as f64;
+        (0..n).map(|i| TotalOrd((i as f64 - mid) / 4.0)).collect()
     }
 
-    /*
-     * Move a leaf up towards the root.
-     */
-    fn sift_in(&mut self, i: usize) {
-        dbg_show_call!(self, "sift_in-start({}, ", i);
-        let mut p;
-        let mut n = i;
-        let c = self.c;
-        while n != c {
-            p = get_parent(n, c);
-            if self.bt(n, p) {
-                dbg_println!("sift_in: child {} better than parent {}", n, p);
-                // Violation: child is "better than" parent.
-                self.swap(n, p);
-                n = p;
-            } else {
-                dbg_println!("sift_in: parent {} better than child {}, ending", p, n);
-                break;
-            }
-        }
-        dbg_show_call!(self, "sift_in-end({}, ", i);
+    fn parse(token: &str) -> Option<Self> {
+        token.parse().ok().map(TotalOrd)
     }
 
-    /*
-     * Given our range:
-     *
-     *      [x, x, C, x, x]
-     *       ^lo
-     *
-     * Swap the center (best) value into lo and shrinks the range on the left.
-     *
-     *      C  [x, x, x, x]
-     *
-     * Side-effect: Adjust lo to be lo + 1.
-     *
-     * Side-effect: May re-center.
-     */
-    fn pop_left(&mut self) {
-        #[cfg(debug_assertions)]
-        self.check();
-
-        assert!(!self.is_empty(), "c-heap error: pop when empty");
-
-        let lop = self.lo + 1;
-        if self.lo == self.c {
-            if lop < self.hi {
-                self.c = self.hi - 1;
-                self.lo = lop;
-                self.recenter();
-            } else {
-                self.lo = lop; // Now empty.
-                self.c = lop;
-            }
-        } else {
-            self.swap(self.c, self.lo);
-            self.lo = lop;
-            self.sift_out(self.c);
-        }
-        #[cfg(debug_assertions)]
-        self.check();
+    fn from_json(v: &JsonValue) -> Option<Self> {
+        v.as_f64().map(TotalOrd)
     }
 
-    /*
-     * Given our range:
-     *
-     *      [x, x, C, x, x] . . .
-     *                      ^hi
-     *
-     * Swap the center (best) value into hi - 1 and shrinks the range on the right.
-     *
-     *      [x, x, x, x] C . . .
-     *                   ^hi
-     *
-     * Side-effect: Adjusts hi to be hi - 1.
-     *
-     * Side-effect: May re-center.
-     */
-    fn pop_right(&mut self) {
-        #[cfg(debug_assertions)]
-        self.check();
-        assert!(!self.is_empty(), "c-heap error: pop when empty");
-        let hip = self.hi - 1;
-        if hip == self.c {
-            self.c = self.lo;
-            self.hi = hip;
-            if self.lo < hip {
-                self.recenter();
-            } // else now empty.
-        } else {
-            self.a.swap(hip as usize, self.c as usize);
-            self.hi = hip;
-            self.sift_out(self.c);
-        }
-        #[cfg(debug_assertions)]
-        self.check();
+    fn to_text(&self) -> String {
+        self.0.to_string()
     }
+}
 
-    /*
-     * Given our range:
-     *
-     *      L [x, x, x, x]
-     *         ^lo
-     *
-     * Expand the range to absorb L and preserve invariants.
-     *
-     *     [x, x, x, L, x]
-     *
-     * Side-effect: adjust lo to lo - 1.
-     *
-     * Side-effect: may adjust center index when pushing into an empty container.
-     */
-    fn push_left(&mut self) {
-        #[cfg(debug_assertions)]
-        self.check();
-        assert!(
-            self.lo > 0,
-            "c-heap error: attempt to push past array boundary"
-        );
-
-        let lop = self.lo - 1;
-        if self.c == self.hi {
-            debug_assert!(self.lo == self.c, "c-heap state: expected an empty c-heap");
-            self.c = lop;
-        }
-        self.lo = lop;
-        self.sift_in(lop);
-        #[cfg(debug_assertions)]
-        self.check();
+/*
+ * Random lowercase strings of `str_len` letters, sorted. Short strings may repeat, even in a
+ * `shuffle` array.
+ */
+impl Element for String {
+    const NAME: &'static str = "string";
+
+    fn ascending<R: Rng>(n: usize, str_len: usize, rng: &mut R) -> Vec<Self> {
+        let mut v: Vec<String> = (0..n)
+            .map(|_| (0..str_len).map(|_| rng.gen_range('a'..='z')).collect())
+            .collect();
+        v.sort();
+        v
     }
 
-    #[allow(dead_code)]
-    fn push_left_swap(&mut self, i: usize) {
-        assert!(
-            i < self.lo || i >= self.hi,
-            "c-heap error: attempt to swap in value already inside c-heap"
-        );
-        self.swap(i, self.lo - 1);
-        self.push_left();
+    fn parse(token: &str) -> Option<Self> {
+        Some(token.to_string())
     }
 
-    fn push_right_swap(&mut self, i: usize) {
-        assert!(
-            i < self.lo || i >= self.hi,
-            "c-heap error: attempt to swap in value already inside c-heap"
-        );
-        self.swap(i, self.hi);
-        self.push_right();
+    fn from_json(v: &JsonValue) -> Option<Self> {
+        v.as_str().map(str::to_string)
     }
 
-    /*
-     * Given our range:
-     *
-     *     [x, x, x, x] R
-     *                hi^
-     *
-     * Expand the range to absorb R and preserve invariants.
-     *
-     *     [x, R, x, x, x]
-     *
-     * Side-effect: adjust lo to lo - 1.
-     *
-     * Side-effect: may adjust center index when pushing into an empty container.
-     */
-    fn push_right(&mut self) {
-        #[cfg(debug_assertions)]
-        self.check();
-        assert!(
-            self.hi < self.a.len(),
-            "c-heap error: attempt to push when c-heap full"
-        );
-
-        let hip = self.hi + 1;
-        self.sift_in(self.hi);
-        self.hi = hip;
-        #[cfg(debug_assertions)]
-        self.check();
+    fn to_text(&self) -> String {
+        self.clone()
     }
+}
 
-    /*
-     * Given our range:
-     *
-     *      [x, x, C, x, x] . . . . i
-     *
-     * Swap the value at i with the value at C and preserve invariants.
-     *
-     *      [x, x, i, x, x] . . . . C
-     *
-     * This is equivalent to saving the value at index i, popping the best value into i,
-     * and then pushing the saved value back into the heap.
-     *
-     * After this operation, the value at i will always be drawn from the c-heap.
-     *
-     * These semantics mean it does not work with an empty c-heap.
-     *
-     * Guarantees no change to the range.
-     */
-    fn poppush(&mut self, i: usize) {
-        #[cfg(debug_assertions)]
-        self.check();
-        dbg_show_call!(self, "poppush(i={}, ", i);
-        // We could do nothing, but the caller is expecting the best value from the c-heap.
-        assert!(
-            !self.is_empty(),
-            "c-heap error: attempted to pop from an empty range"
-        );
-        assert!(
-            i < self.lo || i >= self.hi,
-            "c-heap error: attempted to push an index already inside c-heap"
-        );
-        self.swap(i, self.c);
-        self.sift_out(self.c);
-        #[cfg(debug_assertions)]
-        self.check();
+/*
+ * A 128 byte record ordered by its key alone, so moves cost far more than compares.
+ */
+#[derive(Debug, Clone)]
+struct Record {
+    key: u64,
+    #[allow(dead_code)]
+    payload: [u64; 15],
+}
+
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
     }
+}
 
-    /*
-     * Given our range:
-     *
-     *      [x, x, C, x, x] . . . . i
-     *
-     * Compare the values at C and i. If i is better than C, do nothing.
-     *
-     * Otherwise, swap the value at i with the value at C and preserve invariants.
-     *
-     *      [x, x, i, x, x] . . . . C
-     *
-     * This is equivalent to pushing i's value into the heap, and then popping the best value
-     * from the heap.
-     *
-     * These semantics mean that nothing will happen if the c-heap is empty or if i is already
-     * better than a value on the c-heap.
-     *
-     * Guarantees no change to the range.
-     */
-    #[allow(dead_code)]
-    fn pushpop(&mut self, i: usize) {
-        #[cfg(debug_assertions)]
-        self.check();
-        dbg_show_call!(self, "pushpop(i={}, ", i);
-        assert!(
-            i < self.lo || i >= self.hi,
-            "c-heap error: attempted to push an index already inside c-heap"
-        );
-        if self.is_empty() || self.bt(i, self.c) {
-            return;
-        }
-        self.swap(i, self.c);
-        self.sift_out(self.c);
-        #[cfg(debug_assertions)]
-        self.check();
+impl Eq for Record {}
+
+impl PartialOrd for Record {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    /*
-     * Given our range:
-     *
-     *      [x, x, x, x, x] R
-     *
-     * Transfer the right-hand value over to the left:
-     *
-     *      R [x, x, x, x, x]
-     *
-     * Side-effect: adjusts lo and hi to be lo + 1 and hi + 1.
-     *
-     * Side-effect: may recenter the heap.
-     */
-    fn slide_right(&mut self) {
-        #[cfg(debug_assertions)]
-        self.check();
-        assert!(
-            self.hi < self.a.len(),
-            "c-heap error: attempt to slide right past array bounds"
-        );
-        if self.is_empty() {
-            self.lo += 1;
-            self.c += 1;
-            self.hi += 1;
-        } else {
-            let lop = self.lo + 1;
-            let hip = self.hi + 1;
-            self.swap(self.lo, self.hi);
-            if self.c == self.lo {
-                self.c = self.hi;
-                self.lo = lop;
-                self.hi = hip;
-                self.recenter();
-            } else {
-                self.sift_in(self.hi);
-                self.lo = lop;
-                self.hi = hip;
-            }
-        }
-        #[cfg(debug_assertions)]
-        self.check();
+impl Ord for Record {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
     }
+}
 
-    /*
-     * Given our range:
-     *
-     *      L [x, x, x, x, x]
-     *
-     * Transfer the left-hand value over to the right:
-     *
-     *      [x, x, x, x, x] L
-     *
-     * Side-effect: adjusts lo and hi to be lo - 1 and hi - 1.
-     *
-     * Side-effect: may recenter the heap.
-     */
-    #[allow(dead_code)]
-    fn slide_left(&mut self) {
-        #[cfg(debug_assertions)]
-        self.check();
-        assert!(
-            self.lo > 0,
-            "c-heap error: attempt to slide left past array bounds"
-        );
-        if self.is_empty() {
-            self.lo -= 1;
-            self.c -= 1;
-            self.hi -= 1;
-        } else {
-            let lop = self.lo - 1;
-            let hip = self.hi - 1;
-            self.swap(lop, hip);
-            if self.c == hip {
-                self.c = self.lo;
-                self.lo = lop;
-                self.hi = hip;
-                self.recenter();
-            } else {
-                self.sift_in(lop);
-                self.lo = lop;
-                self.hi = hip;
-            }
+impl Record {
+    fn new(key: u64) -> Self {
+        Record {
+            key,
+            payload: [key; 15],
         }
-        #[cfg(debug_assertions)]
-        self.check();
     }
+}
 
-    /*
-     * Given lo:md is sorted and md:hi is sorted, merge them.
-     *
-     * Uses a centered heap.
-     *
-     * ---|------|------|-----|---
-     *    lo   ch.lo  ch.hi  hi
-     */
-    fn merge(a: &mut [E], lo: usize, md: usize, hi: usize, cnt: &mut C) {
-        dbg_println!("merge({}, {}, {})", lo, md, hi);
-        let mut ch = Cheap {
-            a: a,
-            lo: md,
-            c: md,
-            hi: md,
-            cnt: cnt,
-        };
-        debug_assert!(is_sorted(ch.a, lo, md), "merge(pre): lo to md not sorted");
-        debug_assert!(is_sorted(ch.a, md, hi), "merge(pre): md to hi not sorted");
+/*
+ * Records are read and written as their keys.
+ */
+impl Element for Record {
+    const NAME: &'static str = "record";
 
-        for ix in lo..hi {
-            if ix >= ch.hi {
-                // Only one vector left, nothing to do.
-                break;
-            }
+    fn ascending<R: Rng>(n: usize, _: usize, _: &mut R) -> Vec<Self> {
+        (0..n as u64).map(Record::new).collect()
+    }
 
-            let mut best = MC::None;
+    fn parse(token: &str) -> Option<Self> {
+        token.parse().ok().map(Record::new)
+    }
 
-            if ix < ch.lo {
-                best = MC::Lo(&ch.a[ix]);
-            }
-            if ch.lo < ch.hi {
-                best = best.better(MC::Md(&ch.a[ch.c]), ch.cnt);
-            }
-            if ch.hi < hi {
-                best = best.better(MC::Hi(&ch.a[ch.hi]), ch.cnt);
-            }
-            if let MC::None = best {
-                panic!("merge: logic error");
-            }
-            if let MC::Lo(_) = best {
-                dbg_println!("merge: output is in place");
-                continue;
-            } else if ix < ch.lo {
-                if let MC::Md(_) = best {
-                    // Pop the best value from ch into ix, and push the value that was at ix in.
-                    dbg_println!("merge: poppush");
-                    ch.poppush(ix);
-                } else {
-                    // Swap the right hand value into ix
-                    dbg_println!("merge: push_right");
-                    ch.push_right_swap(ix);
-                }
-            } else if ix == ch.lo {
-                if let MC::Md(_) = best {
-                    // We're in ch, so just pop a value in place.
-                    dbg_println!("merge: pop_left");
-                    ch.pop_left();
-                } else {
-                    // We just need to move the right hand value into place.
-                    dbg_println!("merge: slide_right");
-                    ch.slide_right();
-                }
-            } else {
-                panic!("merge: ix is invalid!");
-            }
-        }
-        debug_assert!(is_sorted(a, lo, hi), "merge(post): not sorted after merge");
+    fn from_json(v: &JsonValue) -> Option<Self> {
+        v.as_u64().map(Record::new)
+    }
+
+    fn to_text(&self) -> String {
+        self.key.to_string()
     }
 }
 
-// Convenience for formatting a single entity.
-macro_rules! one_ent {
-    ($self: ident, $i: expr, $f:ident) => {
-        $f.write_str(format!("{:?}", &$self.a[$i as usize]).as_str())?;
-        let mut dot = ":";
-        if $i == $self.lo {
-            $f.write_str(":lo")?;
-            dot = ".";
-        }
-        if $i == $self.c {
-            $f.write_str(dot)?;
-            $f.write_str("c")?;
-            dot = ".";
-        }
-        if $i == $self.hi {
-            $f.write_str(dot)?;
-            $f.write_str("hi")?;
-        }
-    };
+#[derive(Display)]
+enum ElemType {
+    I32,
+    U64,
+    F64,
+    Str,
+    Record,
+    Unknown,
 }
 
-impl<'a, E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug> fmt::Debug for Cheap<'a, E, C> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let a_len = self.a.len();
-
-        if a_len < 1 {
-            f.write_str("[]")?;
-        } else if a_len < 100 {
-            f.write_str("[")?;
-            one_ent!(self, 0, f);
-            for i in 1..a_len {
-                f.write_str(" ")?;
-                one_ent!(self, i, f);
-            }
-            f.write_str("]")?;
-        } else {
-            f.write_str("[")?;
-            one_ent!(self, 0, f);
-            for i in 1..40 {
-                f.write_str(" ")?;
-                one_ent!(self, i, f);
-            }
-            f.write_str(" ...")?;
-            for i in a_len - 40..a_len {
-                f.write_str(" ")?;
-                one_ent!(self, i, f);
-            }
-            f.write_str("]")?;
+impl ElemType {
+    fn from_str(ty: Option<&str>) -> Self {
+        match ty {
+            Some(ty_str) => match ty_str {
+                "i32" => ElemType::I32,
+                "u64" => ElemType::U64,
+                "f64" => ElemType::F64,
+                "string" => ElemType::Str,
+                "record" => ElemType::Record,
+                _ => ElemType::Unknown,
+            },
+            None => ElemType::Unknown,
         }
-        Ok(())
     }
 }
 
-#[derive(Debug)]
-enum MC<'a, E> {
-    None,      // -1
-    Lo(&'a E), // 0
-    Md(&'a E), // 1
-    Hi(&'a E), // 2
+/*
+ * Where each of `k` equal runs after the first starts.
+ */
+fn run_bounds(len: usize, k: usize) -> Vec<usize> {
+    let k = k.max(1);
+    (1..k).map(|i| i * len / k).collect()
 }
 
-impl<'a, E: PartialOrd> MC<'a, E> {
-    fn val(&self) -> Option<&'a E> {
-        match self {
-            MC::None => None,
-            MC::Lo(v) => Some(v),
-            MC::Md(v) => Some(v),
-            MC::Hi(v) => Some(v),
-        }
+/*
+ * Merge the runs into the sorted prefix one at a time, with a call to `merge` for each.
+ */
+fn merge_pairwise<E: Ord + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    bounds: &[usize],
+    merge: MergeStrategy,
+    cnt: &mut C,
+) {
+    let merge = merge.merge_fn();
+    let a_len = a.len();
+    for (i, &md) in bounds.iter().enumerate() {
+        let hi = bounds.get(i + 1).copied().unwrap_or(a_len);
+        merge(a, 0, md, hi, cnt);
     }
+}
 
-    fn better<C: Counter>(self, other: Self, cnt: &mut C) -> Self {
-        match (self.val(), other.val()) {
-            (None, None) => MC::None,
-            (Some(_), None) => self,
-            (None, Some(_)) => other,
-            (Some(a), Some(b)) => {
-                cnt.count_compare();
-                if a < b {
-                    self
-                } else {
-                    other
-                }
-            }
-        }
-    }
+/*
+ * Where the array comes from and goes to, when it isn't generated or thrown away.
+ */
+struct Files {
+    /// The text read from `--input`.
+    input: Option<String>,
+    /// The path given to `--output`, where `-` is stdout.
+    output: Option<String>,
+    /// The path given to `--trace`.
+    trace: Option<String>,
 }
 
-fn small_sort<E: PartialOrd, C: Counter>(a: &mut [E], lo: usize, hi: usize, c: &mut C) {
-    debug_assert!(
-        /*0 <= lo && */ lo <= hi && hi <= a.len(),
-        "small_sort(pre): length invariants"
-    );
-
-    for i in lo + 1..hi {
-        let mut j = i;
-        while j > lo && a[j] < a[j - 1] {
-            c.count_compare();
-            a.swap(j - 1, j);
-            c.count_swap();
-            j -= 1;
+/*
+ * Read an array either from a JSON array or from whitespace separated tokens.
+ */
+fn parse_array<E: Element>(text: &str) -> Result<Vec<E>, String> {
+    if text.trim_start().starts_with('[') {
+        let v = json::parse(text).map_err(|e| format!("Bad JSON input: {}", e))?;
+        if !v.is_array() {
+            return Err("JSON input isn't an array".to_string());
         }
-        c.count_compare();
+        v.members()
+            .map(|m| E::from_json(m).ok_or_else(|| format!("Bad element in input: {}", m)))
+            .collect()
+    } else {
+        text.split_whitespace()
+            .map(|t| E::parse(t).ok_or_else(|| format!("Bad element in input: {}", t)))
+            .collect()
     }
-    debug_assert!(is_sorted(a, lo, hi), "small_sort(post): not sorted");
 }
 
-fn is_sorted<E: PartialOrd>(a: &[E], lo: usize, hi: usize) -> bool {
-    assert!(
-        /*0 <= lo && */ lo <= hi && hi <= a.len(),
-        "is_sorted(pre): length invariants"
-    );
-
-    let mut v = &a[lo];
-    for i in lo + 1..hi {
-        let vv = &a[i];
-        if vv < v {
-            return false;
-        }
-        v = vv;
+/*
+ * Write the array one element per line.
+ */
+fn write_array<E: Element>(a: &[E], path: &str) -> io::Result<()> {
+    let w: Box<dyn Write> = if path == "-" {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(path)?)
+    };
+    let mut w = BufWriter::new(w);
+    for e in a {
+        writeln!(w, "{}", e.to_text())?;
     }
-    return true;
+    w.flush()
 }
 
-fn merge_sort<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
-    a: &mut [E],
-    lo: usize,
-    hi: usize,
-    merge: fn(&mut [E], lo: usize, md: usize, hi: usize, cnt: &mut C),
-    cnt: &mut C,
-) {
-    debug_assert!(
-        /*0 <= lo && */ lo <= hi && hi <= a.len(),
-        "merge_sort(pre): length invariants"
-    );
-    if hi - lo <= 4 {
-        small_sort(a, lo, hi, cnt);
-        return;
+/*
+ * The elements as the text `Element::parse` reads.
+ */
+fn texts<E: Element>(a: &[E]) -> JsonValue {
+    a.iter().map(E::to_text).collect::<Vec<String>>().into()
+}
+
+/*
+ * Run `op` on `n` and trace it to `path`. The trace starts with a header holding the array as
+ * it was and ends with the array as it is, so `cheap replay` needs nothing else.
+ */
+fn trace_workload<E: Element>(
+    op: &Op,
+    n: &mut Vec<E>,
+    params: &Params,
+    path: &str,
+) -> Result<RealCounter, String> {
+    if !op.is_traceable() {
+        return Err(format!(
+            "{} can't be traced, as replay couldn't follow it",
+            op
+        ));
     }
+    let fail = |e: io::Error| format!("Can't write {}: {}", path, e);
+    let mut w = BufWriter::new(File::create(path).map_err(fail)?);
+    let header = object! {
+        "trace" => op.to_string(),
+        "type"  => E::NAME,
+        "input" => texts(n),
+    };
+    writeln!(w, "{}", header).map_err(fail)?;
+    let mut tr = Tracer::with_counter(w, RealCounter::default());
+    op.run(n, params, &mut tr);
+    let (mut w, cnt) = tr.finish().map_err(fail)?;
+    writeln!(w, "{}", object! { "output" => texts(n) }).map_err(fail)?;
+    w.flush().map_err(fail)?;
+    Ok(cnt)
+}
 
-    let midpoint = (lo + hi) / 2;
-    dbg_println!("merge_sort: lo={}, md={}, hi={}", lo, midpoint, hi);
-    merge_sort(a, lo, midpoint, merge, cnt);
-    merge_sort(a, midpoint, hi, merge, cnt);
-    merge(a, lo, midpoint, hi, cnt);
-    debug_assert!(is_sorted(a, lo, hi), "merge_sort(post): not sorted");
+/*
+ * Read elements written by `texts`.
+ */
+fn parse_texts<E: Element>(v: &JsonValue) -> Result<Vec<E>, String> {
+    v.members()
+        .map(|m| {
+            m.as_str()
+                .and_then(E::parse)
+                .ok_or_else(|| format!("Bad element in trace: {}", m))
+        })
+        .collect()
 }
 
-fn heap_sort_left<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(a: &mut [E], cnt: &mut C) {
-    let mut c: Cheap<E, C> = Cheap::new_spanright(a, cnt);
-    c.recenter();
-    while !c.is_empty() {
-        c.pop_left();
+/*
+ * Replay a trace written by `trace_workload` from its own copy of the input, and check the array
+ * ends up the way the trace says.
+ */
+fn replay_as<E: Element>(header: &JsonValue, text: &str) -> Result<JsonValue, String> {
+    let mut a: Vec<E> = parse_texts(&header["input"])?;
+    let stats = replay(&mut a, text.lines()).map_err(|e| e.to_string())?;
+    let footer = text
+        .lines()
+        .last()
+        .and_then(|l| json::parse(l).ok())
+        .filter(|f| f.has_key("output"))
+        .ok_or("Trace has no output at the end; was it cut short?")?;
+    let expect: Vec<E> = parse_texts(&footer["output"])?;
+    if expect.len() != a.len() {
+        return Err("Trace output isn't the same size as its input".to_string());
+    }
+    if let Some(i) = (0..a.len()).find(|&i| a[i] != expect[i]) {
+        return Err(format!(
+            "Replay ends with {} at index {}, but the trace ends with {}",
+            a[i].to_text(),
+            i,
+            expect[i].to_text()
+        ));
     }
+    Ok(object! {
+        "op"        => header["trace"].clone(),
+        "type"      => E::NAME,
+        "num_elems" => a.len(),
+        "calls"     => stats.calls,
+        "swaps"     => stats.swaps,
+        "reversals" => stats.reversals,
+        "lines"     => stats.lines,
+        "replayed"  => true,
+    })
 }
 
-fn heap_sort_right<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(a: &mut [E], cnt: &mut C) {
-    let mut c: Cheap<E, C> = Cheap::new_spanleft(a, cnt);
-    c.recenter();
-    while !c.is_empty() {
-        c.pop_right();
-    }
-    a.reverse();
+/*
+ * Replay the trace at `path`, or on stdin if it's `-`.
+ */
+fn replay_trace(path: &str) -> Result<JsonValue, String> {
+    let text = read_input(path)?;
+    let header = text
+        .lines()
+        .next()
+        .and_then(|l| json::parse(l).ok())
+        .filter(|h| h.has_key("trace"))
+        .ok_or_else(|| format!("{} isn't a trace", path))?;
+    let mut out = match ElemType::from_str(header["type"].as_str()) {
+        ElemType::I32 => replay_as::<i32>(&header, &text),
+        ElemType::U64 => replay_as::<u64>(&header, &text),
+        ElemType::F64 => replay_as::<TotalOrd<f64>>(&header, &text),
+        ElemType::Str => replay_as::<String>(&header, &text),
+        ElemType::Record => replay_as::<Record>(&header, &text),
+        ElemType::Unknown => Err("Unknown type in trace".to_string()),
+    }?;
+    out["trace"] = path.into();
+    Ok(out)
 }
 
-/**
- * This running sort starts at the left, pushes from the right until it's `run` elements large,
- * then pops elements.
+/*
+ * Read a whole file, or stdin if `path` is `-`.
  */
-fn running_sort_left<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
-    a: &mut [E],
-    run: usize,
-    cnt: &mut C,
-) {
-    let a_len = a.len();
-    if a_len == 0 {
-        return;
+fn read_input(path: &str) -> Result<String, String> {
+    if path == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Can't read stdin: {}", e))?;
+        Ok(text)
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path, e))
     }
-    let mut c: Cheap<E, C> = Cheap::new_left(a, cnt);
-    while c.lo < a_len {
-        if c.hi < a_len {
-            c.push_right();
-        }
-        if c.hi - c.lo >= run || c.hi == a_len {
-            c.pop_left();
-        }
-        if a_len < 200 {
-            show_call!(c, "running_left(");
-        }
+}
+
+/*
+ * Check the settings that only make sense for some array lengths.
+ */
+fn check_len(op: &Op, params: &Params, n_len: usize) -> Result<(), String> {
+    match op {
+        Op::SelectNth if params.nth.unwrap_or(n_len / 2) >= n_len => Err(format!(
+            "NTH must be less than the number of elements, {}",
+            n_len
+        )),
+        _ => Ok(()),
     }
 }
 
-/**
- * This running sort starts at the right, pushes from the left until it's `run` elements large,
- * then pops elements.
+/*
+ * Build or read the array with elements of type `E`, run the operation over it and report.
  */
-fn running_sort_right<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
-    a: &mut [E],
-    run: usize,
-    cnt: &mut C,
-) {
-    let a_len = a.len();
-    if a_len == 0 {
-        return;
+fn run_workload<E: Element>(
+    op: &Op,
+    ac: &ArrayCon,
+    ap: &ArrayParams,
+    params: &Params,
+    count: bool,
+    files: &Files,
+) -> Result<JsonValue, String> {
+    let mut n: Vec<E> = match &files.input {
+        Some(text) => parse_array(text)?,
+        None => ac.make_array(ap),
+    };
+    let n_len = n.len();
+    check_len(op, params, n_len)?;
+    let (run_size, k) = (params.run_size, params.k);
+    let nth = params.nth.unwrap_or(n_len / 2);
+    if let Op::MergeRuns = op {
+        let mut lo = 0;
+        for hi in run_bounds(n_len, k).into_iter().chain(iter::once(n_len)) {
+            n[lo..hi].sort();
+            lo = hi;
+        }
     }
-    let mut c: Cheap<E, C> = Cheap::new_right(a, cnt);
-    while c.hi > 0 {
-        if c.lo > 0 {
-            c.push_left();
+    let unmerged = n.clone();
+
+    let mut out = object! {
+        "op"    => op.to_string(),
+        "array" => ac.to_string(),
+        "num_elems"     => n_len,
+    };
+    ac.report(ap, &mut out);
+
+    let now = SystemTime::now();
+    if let Some(path) = &files.trace {
+        let cnt = trace_workload(op, &mut n, params, path)?;
+        if count {
+            cnt.copy_to(&mut out);
+        }
+        out["trace"] = path.as_str().into();
+    } else if count {
+        let mut cnt = RealCounter::default();
+        op.run(&mut n, params, &mut cnt);
+        cnt.copy_to(&mut out);
+        // The std slice sorts move elements where they can't be counted.
+        if let Op::Sort | Op::SortUnstable = op {
+            out.remove("moves");
         }
-        if c.hi - c.lo >= run || c.lo == 0 {
-            c.pop_right();
+        if op.is_window() {
+            let steps = (n_len + 1).saturating_sub(run_size).max(1) as f64;
+            out["compares_per_step"] = (cnt.compares as f64 / steps).into();
+            out["swaps_per_step"] = (cnt.swaps as f64 / steps).into();
         }
-        if a_len < 200 {
-            show_call!(c, "running_right(");
+        if let Op::MergeRuns = op {
+            let mut m = unmerged;
+            let mut pcnt = RealCounter::default();
+            merge_pairwise(&mut m, &run_bounds(n_len, k), params.merge, &mut pcnt);
+            out["pairwise_compares"] = pcnt.compares.into();
+            out["pairwise_swaps"] = pcnt.swaps.into();
         }
+    } else {
+        op.run(&mut n, params, &mut DummyCounter {});
+    }
+    if let Ok(elapsed) = now.elapsed() {
+        out["elapsed"] = elapsed.as_secs_f64().into();
+    }
+
+    if let Op::TopK = op {
+        out["k"] = k.into();
+        out["is_top_k"] = JsonValue::Boolean(is_partially_sorted(&n, k));
+    }
+    if op.is_window() {
+        out["run_size"] = run_size.into();
+        out["steps"] = (n_len + 1).saturating_sub(run_size).into();
+    }
+    if let Op::MergeRuns = op {
+        out["runs"] = k.into();
+    }
+    if let Op::MergeSort | Op::BottomUpMergeSort | Op::MergeRuns = op {
+        out["merge"] = params.merge.name().into();
+    }
+    if let (Op::BottomUpMergeSort, Some(block)) = (op, params.block) {
+        out["block"] = block.into();
+    }
+    if let Op::SelectNth = op {
+        out["nth"] = nth.into();
+        out["is_selected"] = JsonValue::Boolean(is_selected(&n, nth));
     }
+    if op.does_sort() {
+        out["is_sorted"] = JsonValue::Boolean(is_sorted(&n, 0, n_len));
+    }
+    if let Some(path) = &files.output {
+        write_array(&n, path).map_err(|e| format!("Can't write {}: {}", path, e))?;
+    }
+    Ok(out)
 }
 
-#[derive(Display)]
-enum Op {
-    MergeSort,
-    HeapSortLeft,
-    HeapSortRight,
-    RunningSortLeft,
-    RunningSortRight,
-    Sort,
-    Unknown,
+enum TableFormat {
+    Markdown,
+    Csv,
+    Json,
 }
 
-impl Op {
-    fn from_str(op: Option<&str>) -> Self {
-        match op {
-            Some(op_str) => match op_str {
-                "merge" => Op::MergeSort,
-                "heap_left" => Op::HeapSortLeft,
-                "heap_right" => Op::HeapSortRight,
-                "run_left" => Op::RunningSortLeft,
-                "run_right" => Op::RunningSortRight,
-                "sort" => Op::Sort,
-                _ => Op::Unknown,
-            },
-            None => Op::Unknown,
+impl TableFormat {
+    fn from_str(f: Option<&str>) -> Self {
+        match f {
+            Some("csv") => TableFormat::Csv,
+            Some("json") => TableFormat::Json,
+            _ => TableFormat::Markdown,
         }
     }
+}
 
-    fn does_sort(self) -> bool {
-        match self {
-            Op::Sort | Op::MergeSort | Op::HeapSortLeft | Op::HeapSortRight => true,
-            _ => false,
-        }
+/*
+ * Mean and sample standard deviation.
+ */
+fn mean_sd(xs: &[f64]) -> (f64, f64) {
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    if xs.len() < 2 {
+        return (mean, 0.0);
     }
+    let var = xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+    (mean, var.sqrt())
+}
 
-    fn run<C: Counter + fmt::Debug, E: Ord + fmt::Debug>(
-        &self,
-        n: &mut [E],
-        run_size: usize,
-        cnt: &mut C,
-    ) {
-        let n_len = n.len();
-        match self {
-            Op::MergeSort => merge_sort(n, 0, n_len, Cheap::<E, C>::merge, cnt),
-            Op::HeapSortLeft => heap_sort_left(n, cnt),
-            Op::HeapSortRight => heap_sort_right(n, cnt),
-            Op::Sort => n.sort(),
-            Op::RunningSortLeft => running_sort_left(n, run_size, cnt),
-            Op::RunningSortRight => running_sort_right(n, run_size, cnt),
-            Op::Unknown => return usage("Unknown operation"),
-        }
-    }
+/*
+ * Round half up to `places` decimal places.
+ */
+fn round_to(x: f64, places: usize) -> f64 {
+    let scale = 10f64.powi(places as i32);
+    (x * scale).round() / scale
 }
 
-#[derive(Display)]
-enum ArrayCon {
-    Shuffle,
-    Random,
-    Count,
-    Reverse,
-    Unknown,
+/*
+ * Round half up to `places` decimal places without trailing zeros, as the README's tables have
+ * it.
+ */
+fn round(x: f64, places: usize) -> String {
+    let s = format!("{:.*}", places, round_to(x, places));
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
 }
 
-impl ArrayCon {
-    fn from_str(ac: Option<&str>) -> Self {
-        match ac {
-            Some(ac_str) => match ac_str {
-                "shuffle" => ArrayCon::Shuffle,
-                "random" => ArrayCon::Random,
-                "count" => ArrayCon::Count,
-                "reverse" => ArrayCon::Reverse,
-                _ => ArrayCon::Unknown,
-            },
-            None => ArrayCon::Unknown,
+/*
+ * Run the workload `reps` times at each power of two size from `min`, itself a power of two,
+ * to `max` and print the compares and swaps per element, and how much they grow from one size
+ * to the next.
+ *
+ * Each run gets its own seed, drawn from the workload's seed.
+ */
+fn sweep(
+    mut w: Workload,
+    min: usize,
+    max: usize,
+    reps: usize,
+    format: &TableFormat,
+) -> Result<(), String> {
+    let seed = w.ap.seed;
+    let mut seeds = StdRng::seed_from_u64(seed);
+    let mut prev: Option<(f64, f64)> = None;
+    let no_files = Files {
+        input: None,
+        output: None,
+        trace: None,
+    };
+    let show_sd = reps > 1;
+    match format {
+        TableFormat::Markdown if show_sd => {
+            println!(concat!(
+                "| Size     | Compares   | ±          | Swaps      | ±          | Compares / N ",
+                "| Swaps / N | Comp grow | Swap grow |"
+            ));
+            println!(concat!(
+                "| -------- | ---------- | ---------- | ---------- | ---------- | ------------ ",
+                "| --------- | --------- | --------- |"
+            ));
+        }
+        TableFormat::Markdown => {
+            println!(concat!(
+                "| Size     | Compares   | Swaps      | Compares / N | Swaps / N | Comp grow ",
+                "| Swap grow |"
+            ));
+            println!(concat!(
+                "| -------- | ---------- | ---------- | ------------ | --------- | --------- ",
+                "| --------- |"
+            ));
         }
+        TableFormat::Csv => println!(concat!(
+            "size,compares,compares_sd,swaps,swaps_sd,compares_per_n,swaps_per_n,",
+            "compares_growth,swaps_growth"
+        )),
+        TableFormat::Json => (),
     }
 
-    /*
-     * Construct an array based on a string integer provided on the command line.
-     */
-    fn make_array(&self, num_elems: usize) -> Vec<i32> {
-        let mut rng = thread_rng();
-        let num_elems_i32 = num_elems as i32;
-
-        let mut a: Vec<i32> = match self {
-            ArrayCon::Shuffle | ArrayCon::Count | ArrayCon::Reverse => (0..num_elems_i32).collect(),
-            ArrayCon::Random => Vec::with_capacity(num_elems),
-            ArrayCon::Unknown => Vec::new(),
+    let mut size = min;
+    while size <= max {
+        let mut compares = Vec::with_capacity(reps);
+        let mut swaps = Vec::with_capacity(reps);
+        for _ in 0..reps {
+            w.ap.size = size;
+            w.ap.seed = seeds.gen();
+            let out = w.run(true, &no_files)?;
+            compares.push(out["compares"].as_f64().unwrap_or(0.0));
+            swaps.push(out["swaps"].as_f64().unwrap_or(0.0));
+        }
+        let (compares, compares_sd) = mean_sd(&compares);
+        let (swaps, swaps_sd) = mean_sd(&swaps);
+        let per_n = (compares / size as f64, swaps / size as f64);
+        // The Markdown table works out growth from the figures per element it shows, so each
+        // row agrees with the one above it.
+        let base = match format {
+            TableFormat::Markdown => (round_to(per_n.0, 2), round_to(per_n.1, 2)),
+            _ => per_n,
         };
-        match self {
-            ArrayCon::Shuffle => {
-                a.shuffle(&mut rng);
-            }
-            ArrayCon::Random => {
-                a.resize(num_elems, 0);
-                for elem in a.iter_mut() {
-                    *elem = rng.gen_range(0..num_elems_i32);
+        let grow = prev.map(|p| (base.0 / p.0, base.1 / p.1));
+        prev = Some(base);
+
+        let grow_text = |g: Option<f64>| g.map_or("-".to_string(), |g| round(g, 3));
+        match format {
+            TableFormat::Markdown => {
+                let mut cells = vec![(size.to_string(), 8), (round(compares, 2), 10)];
+                if show_sd {
+                    cells.push((round(compares_sd, 2), 10));
+                }
+                cells.push((round(swaps, 2), 10));
+                if show_sd {
+                    cells.push((round(swaps_sd, 2), 10));
                 }
+                cells.push((round(per_n.0, 2), 12));
+                cells.push((round(per_n.1, 2), 9));
+                cells.push((grow_text(grow.map(|g| g.0)), 9));
+                cells.push((grow_text(grow.map(|g| g.1)), 9));
+                let row: Vec<String> = cells
+                    .iter()
+                    .map(|(c, width)| format!(" {:<1$} ", c, width))
+                    .collect();
+                println!("|{}|", row.join("|"));
             }
-            ArrayCon::Reverse => {
-                a.reverse();
+            TableFormat::Csv => println!(
+                "{},{},{},{},{},{},{},{},{}",
+                size,
+                compares,
+                compares_sd,
+                swaps,
+                swaps_sd,
+                per_n.0,
+                per_n.1,
+                grow.map_or(String::new(), |g| g.0.to_string()),
+                grow.map_or(String::new(), |g| g.1.to_string()),
+            ),
+            TableFormat::Json => {
+                let mut out = object! {
+                    "op"    => w.op.to_string(),
+                    "array" => w.ac.to_string(),
+                    "type"  => w.ty.to_string(),
+                    "num_elems" => size,
+                    "seed"  => seed,
+                    "reps"  => reps,
+                    "compares"  => compares,
+                    "compares_sd"   => compares_sd,
+                    "swaps" => swaps,
+                    "swaps_sd"  => swaps_sd,
+                    "compares_per_n"    => per_n.0,
+                    "swaps_per_n"   => per_n.1,
+                };
+                if let Some((c, s)) = grow {
+                    out["compares_growth"] = c.into();
+                    out["swaps_growth"] = s.into();
+                }
+                println!("{}", out);
             }
-            _ => (),
         }
-        return a;
+        size = match size.checked_mul(2) {
+            Some(size) => size,
+            None => break,
+        };
     }
+    Ok(())
+}
+
+/*
+ * Run each operation in `ops` on its own copy of the same array, counting as it goes, and print
+ * how they did side by side.
+ */
+fn compare(mut w: Workload, ops: &[&str], format: &TableFormat) -> Result<(), String> {
+    let no_files = Files {
+        input: None,
+        output: None,
+        trace: None,
+    };
+    match format {
+        TableFormat::Markdown => {
+            println!(concat!(
+                "| Op               | Elapsed (s) | Compares   | Swaps      | Moves      ",
+                "| Correct |"
+            ));
+            println!(concat!(
+                "| ---------------- | ----------- | ---------- | ---------- | ---------- ",
+                "| ------- |"
+            ));
+        }
+        TableFormat::Csv => println!("op,elapsed,compares,swaps,moves,correct"),
+        TableFormat::Json => (),
+    }
+    for &name in ops {
+        w.op = Op::from_str(Some(name));
+        if let Op::Unknown = w.op {
+            return Err(format!("Unknown operation {}", name));
+        }
+        let out = w.run(true, &no_files)?;
+        // Each operation reports the check that suits it, if it has one.
+        let correct = ["is_sorted", "is_top_k", "is_selected"]
+            .iter()
+            .find_map(|&check| out[check].as_bool());
+        let correct_text = correct.map_or("-".to_string(), |c| c.to_string());
+        let elapsed = out["elapsed"].as_f64().unwrap_or(0.0);
+        let moves = out["moves"].as_u64();
+        match format {
+            TableFormat::Markdown => println!(
+                "| {:<16} | {:<11} | {:<10} | {:<10} | {:<10} | {:<7} |",
+                name,
+                round(elapsed, 6),
+                out["compares"].to_string(),
+                out["swaps"].to_string(),
+                moves.map_or("-".to_string(), |m| m.to_string()),
+                correct_text
+            ),
+            TableFormat::Csv => println!(
+                "{},{},{},{},{},{}",
+                name,
+                elapsed,
+                out["compares"],
+                out["swaps"],
+                moves.map_or(String::new(), |m| m.to_string()),
+                correct_text
+            ),
+            TableFormat::Json => println!("{}", out),
+        }
+    }
+    Ok(())
 }
 
 fn parse_int(so: Option<&str>, d: usize) -> usize {
-    so.map(|s| s.parse::<usize>().ok()).flatten().unwrap_or(d)
+    so.and_then(|s| s.parse::<usize>().ok()).unwrap_or(d)
 }
 
-fn usage(what: &str) {
+fn usage(what: &str) -> ! {
     eprintln!("Inavlid usage: {}", what);
     eprintln!("Try cheap --help.");
+    process::exit(2)
+}
+
+// Report an error and exit, so scripts see the run failed. Stdout is left alone, since it may
+// be carrying the sorted array.
+fn failure(what: &str) -> ! {
+    eprintln!();
+    eprintln!("Something went wrong unexpectedly: {}", what);
+    process::exit(1)
 }
 
-fn failure(what: &str) {
-    println!("");
-    eprintln!("");
-    eprintln!("Something went wrong unexpectedly: {}", what)
+/*
+ * An operation, the array it runs on and the settings for both.
+ */
+struct Workload {
+    op: Op,
+    ac: ArrayCon,
+    ty: ElemType,
+    ap: ArrayParams,
+    params: Params,
+}
+
+impl Workload {
+    /*
+     * Read the arguments from `workload_args`, or explain what's wrong with them.
+     */
+    fn from_matches(matches: &ArgMatches) -> Result<Self, String> {
+        let op: Op = Op::from_str(matches.value_of("op"));
+        let ac: ArrayCon = ArrayCon::from_str(matches.value_of("array"));
+        if let Op::Unknown = op {
+            return Err("Unknown or unspecified operation.".to_string());
+        }
+        let run_size = parse_int(matches.value_of("run_size"), 16);
+        let k = parse_int(matches.value_of("k"), 10);
+        let nth = matches.value_of("nth").map(|n| parse_int(Some(n), 0));
+        let block = matches.value_of("block").map(|b| parse_int(Some(b), 4));
+
+        let merge = MergeStrategy::from_name(matches.value_of("merge").unwrap_or("cheap"))
+            .ok_or_else(|| "Unknown merge".to_string())?;
+        let params = Params {
+            run_size,
+            k,
+            nth,
+            block,
+            merge,
+        };
+
+        let ty = ElemType::from_str(matches.value_of("type"));
+        if let ElemType::Unknown = ty {
+            return Err("Unknown type".to_string());
+        }
+        let seed = match matches.value_of("seed").map(str::parse::<u64>) {
+            Some(Ok(seed)) => seed,
+            Some(Err(_)) => return Err("SEED must be an unsigned 64 bit integer".to_string()),
+            None => thread_rng().gen(),
+        };
+        let zipf = match matches.value_of("zipf").map(str::parse::<f64>) {
+            Some(Ok(zipf)) if zipf.is_finite() && zipf >= 0.0 => zipf,
+            Some(_) => return Err("ZIPF must be a finite number, 0 or more".to_string()),
+            None => 1.0,
+        };
+        let ap = ArrayParams {
+            size: parse_int(matches.value_of("size"), 40),
+            seed,
+            str_len: parse_int(matches.value_of("str_len"), 16),
+            period: parse_int(matches.value_of("period"), 16),
+            swaps: parse_int(matches.value_of("swaps"), 10),
+            distinct: parse_int(matches.value_of("distinct"), 8),
+            zipf,
+            run_len: parse_int(matches.value_of("run_len"), 32),
+        };
+        Ok(Workload {
+            op,
+            ac,
+            ty,
+            ap,
+            params,
+        })
+    }
+
+    /*
+     * Run the workload with elements of the chosen type and report.
+     */
+    fn run(&self, count: bool, files: &Files) -> Result<JsonValue, String> {
+        let (op, ac, ap, params) = (&self.op, &self.ac, &self.ap, &self.params);
+        let mut out = match self.ty {
+            ElemType::I32 => run_workload::<i32>(op, ac, ap, params, count, files),
+            ElemType::U64 => run_workload::<u64>(op, ac, ap, params, count, files),
+            ElemType::F64 => run_workload::<TotalOrd<f64>>(op, ac, ap, params, count, files),
+            ElemType::Str => run_workload::<String>(op, ac, ap, params, count, files),
+            ElemType::Record => run_workload::<Record>(op, ac, ap, params, count, files),
+            ElemType::Unknown => Err("Unknown type".to_string()),
+        }?;
+        out["type"] = self.ty.to_string().into();
+        if let ElemType::Str = self.ty {
+            out["str_len"] = ap.str_len.into();
+        }
+        Ok(out)
+    }
+}
+
+fn size_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("size")
+        .help("Size of the test array.")
+        .short("s")
+        .long("size")
+        .takes_value(true)
+        .value_name("SIZE")
+        .default_value("40")
+}
+
+fn op_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("op")
+        .help(concat!(
+            "Operation to test centered heap. `merge` implements an in-place merge sort ",
+            "using c-heap, `bottom_up` does the same without recursion, merging blocks ",
+            "of BLOCK elements, and `natural_merge` merges the runs already in the array. ",
+            "`heap_`* performs a heap sort using c-heap from the left or ",
+            "right. `running_`* sorts only a window of RUN_SIZE elements. `top_k` sorts ",
+            "only the K smallest elements into place. `select_nth` moves the NTH smallest ",
+            "element to index NTH. `window_`* finds the minimum or maximum of each ",
+            "RUN_SIZE window. `merge_runs` sorts K runs of the array, then merges them ",
+            "in one pass. `sort` and `sort_unstable` use the standard slice sorts, and ",
+            "`binary_heap` heap sorts with the standard BinaryHeap."
+        ))
+        .short("o")
+        .long("op")
+        .takes_value(true)
+        .possible_values(&[
+            "merge",
+            "natural_merge",
+            "bottom_up",
+            "heap_left",
+            "heap_right",
+            "run_left",
+            "run_right",
+            "top_k",
+            "select_nth",
+            "window_min",
+            "window_max",
+            "merge_runs",
+            "sort",
+            "sort_unstable",
+            "binary_heap",
+        ])
+        .value_name("OPERATION")
+        .default_value("merge")
+}
+
+/*
+ * Arguments that describe a workload: the operation, the array it runs on and its settings.
+ * Both a single run and the subcommands take them, with `op` to pick the operation.
+ */
+fn workload_args<'a, 'b>(op: Arg<'a, 'b>) -> Vec<Arg<'a, 'b>> {
+    vec![
+        op,
+        Arg::with_name("array")
+            .help(concat!(
+                "Method to generate the test array. `shuffle` guarantees no duplicate ",
+                "values, while `random` may have them. `count` is counts from 0 to size - 1. ",
+                "`reverse` is a count from size - 1 to 0. `organ_pipe` counts up to the ",
+                "middle and back down. `sawtooth` counts from 0 to PERIOD - 1 over and over. ",
+                "`nearly_sorted` is a count with SWAPS random pairs swapped. `few_unique` ",
+                "draws from DISTINCT values. `zipf` draws duplicates with a Zipf ",
+                "distribution of exponent ZIPF. `median3_killer` is Musser's worst case for ",
+                "median-of-3 quicksort. `sorted_runs` is a shuffle with every RUN_LEN ",
+                "elements sorted."
+            ))
+            .short("a")
+            .long("array")
+            .value_name("ARRAY")
+            .takes_value(true)
+            .possible_values(&[
+                "shuffle",
+                "random",
+                "count",
+                "reverse",
+                "organ_pipe",
+                "sawtooth",
+                "nearly_sorted",
+                "few_unique",
+                "zipf",
+                "median3_killer",
+                "sorted_runs",
+            ])
+            .default_value("shuffle"),
+        Arg::with_name("period")
+            .help("Period of the `sawtooth` array.")
+            .long("period")
+            .takes_value(true)
+            .value_name("PERIOD")
+            .default_value("16"),
+        Arg::with_name("swaps")
+            .help("Number of random swaps in the `nearly_sorted` array.")
+            .long("swaps")
+            .takes_value(true)
+            .value_name("SWAPS")
+            .default_value("10"),
+        Arg::with_name("distinct")
+            .help("Number of distinct values in the `few_unique` array.")
+            .long("distinct")
+            .takes_value(true)
+            .value_name("DISTINCT")
+            .default_value("8"),
+        Arg::with_name("zipf")
+            .help("Exponent of the Zipf distribution for the `zipf` array, 0 or more.")
+            .long("zipf")
+            .takes_value(true)
+            .value_name("ZIPF")
+            .default_value("1.0"),
+        Arg::with_name("run_len")
+            .help("Length of each sorted run in the `sorted_runs` array.")
+            .long("run-len")
+            .takes_value(true)
+            .value_name("RUN_LEN")
+            .default_value("32"),
+        Arg::with_name("type")
+            .help(concat!(
+                "Type of the array elements. `f64` orders NaN after +inf and -0.0 before ",
+                "+0.0. `string` is random strings of STR_LEN lowercase letters. `record` is ",
+                "a 128 byte struct ordered by a u64 key."
+            ))
+            .short("t")
+            .long("type")
+            .takes_value(true)
+            .possible_values(&["i32", "u64", "f64", "string", "record"])
+            .value_name("TYPE")
+            .default_value("i32"),
+        Arg::with_name("str_len")
+            .help("Length of each string when TYPE is `string`.")
+            .long("str-len")
+            .takes_value(true)
+            .value_name("STR_LEN")
+            .default_value("16"),
+        Arg::with_name("seed")
+            .help(concat!(
+                "Seed for the random number generator that builds the test array. Without ",
+                "it, a seed is picked at random. Either way it's reported, so the run can be ",
+                "repeated."
+            ))
+            .long("seed")
+            .takes_value(true)
+            .value_name("SEED"),
+        Arg::with_name("run_size")
+            .help("Size of the window for a running sort or a sliding window.")
+            .short("r")
+            .long("run-size")
+            .takes_value(true)
+            .value_name("RUN_SIZE")
+            .default_value("16"),
+        Arg::with_name("k")
+            .help("Number of elements to select for `top_k`, or of runs for `merge_runs`.")
+            .short("k")
+            .long("k")
+            .takes_value(true)
+            .value_name("K")
+            .default_value("10"),
+        Arg::with_name("merge")
+            .help(concat!(
+                "In-place merge for `merge`, `bottom_up` and the pairwise baseline of ",
+                "`merge_runs`. `cheap` is the c-heap merge, `sym_merge` and `block_swap` ",
+                "merge by rotations, and `buffered` uses O(n) scratch space."
+            ))
+            .short("m")
+            .long("merge")
+            .takes_value(true)
+            .possible_values(&["cheap", "sym_merge", "block_swap", "buffered"])
+            .value_name("MERGE")
+            .default_value("cheap"),
+        Arg::with_name("block")
+            .help("Size of the blocks `bottom_up` sorts before merging. Defaults to 4.")
+            .short("b")
+            .long("block")
+            .takes_value(true)
+            .value_name("BLOCK"),
+        Arg::with_name("nth")
+            .help("Index to select for `select_nth`. Defaults to the median, SIZE / 2.")
+            .short("n")
+            .long("nth")
+            .takes_value(true)
+            .value_name("NTH"),
+    ]
 }
 
 fn main() {
     let matches = App::new("cheap")
         .about("Demonstrate the centered heap data structure.")
-        .arg(
-            Arg::with_name("op")
-                .help(concat!(
-                    "Operation to test centered heap. `merge` implements an in-place merge sort ",
-                    "using c-heap. `heap_`* performs a heap sort using c-heap from the left or ",
-                    "right. `running_`* sorts only a window of RUN_SIZE elements. `sort` uses ",
-                    "the standard Vec::sort method."
+        .args(&workload_args(op_arg()))
+        .subcommand(
+            SubCommand::with_name("sweep")
+                .about(concat!(
+                    "Run the workload over sizes from MIN to MAX, doubling each time, and print ",
+                    "compares and swaps per element with their growth from size to size."
                 ))
-                .short("o")
-                .long("op")
-                .takes_value(true)
-                .possible_values(&[
-                    "merge",
-                    "heap_left",
-                    "heap_right",
-                    "run_left",
-                    "run_right",
-                    "sort",
-                ])
-                .value_name("OPERATION")
-                .default_value("merge"),
+                .args(&workload_args(op_arg()))
+                .arg(
+                    Arg::with_name("min")
+                        .help("Smallest size, rounded up to a power of two.")
+                        .long("min")
+                        .takes_value(true)
+                        .value_name("MIN")
+                        .default_value("4"),
+                )
+                .arg(
+                    Arg::with_name("max")
+                        .help("Largest size.")
+                        .long("max")
+                        .takes_value(true)
+                        .value_name("MAX")
+                        .default_value("32768"),
+                )
+                .arg(
+                    Arg::with_name("reps")
+                        .help(concat!(
+                            "Number of runs at each size. The table shows the mean and standard ",
+                            "deviation across them."
+                        ))
+                        .long("reps")
+                        .takes_value(true)
+                        .value_name("REPS")
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Print a Markdown table, CSV, or a JSON object per size.")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["markdown", "csv", "json"])
+                        .value_name("FORMAT")
+                        .default_value("markdown"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about(concat!(
+                    "Run several operations on identical copies of one array and show their ",
+                    "time, compares, swaps and whether they got the right answer side by side."
+                ))
+                .args(&workload_args(
+                    op_arg()
+                        .help(concat!(
+                            "Comma separated operations to compare. See cheap --help for what ",
+                            "each one does."
+                        ))
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .require_delimiter(true)
+                        .default_value("merge,heap_left,sort"),
+                ))
+                .arg(size_arg())
+                .arg(
+                    Arg::with_name("format")
+                        .help("Print a Markdown table, CSV, or a JSON object per operation.")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["markdown", "csv", "json"])
+                        .value_name("FORMAT")
+                        .default_value("markdown"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about(concat!(
+                    "Run the c-heap operations in a trace written by --trace again on the same ",
+                    "input, and check they do the same things and leave the same array."
+                ))
+                .arg(
+                    Arg::with_name("trace")
+                        .help("The trace to replay, or `-` to read it from stdin.")
+                        .required(true)
+                        .value_name("TRACE"),
+                ),
         )
+        .arg(size_arg())
         .arg(
-            Arg::with_name("array")
+            Arg::with_name("input")
                 .help(concat!(
-                    "Method to generate the test array. `shuffle` guarantees no duplicate ",
-                    "values, while `random` may have them. `count` is counts from 0 to size - 1. ",
-                    "`reverse` is a count from size - 1 to 0."
+                    "Read the array from a file, or from stdin if INPUT is `-`, instead of ",
+                    "generating it. The file holds either whitespace separated elements or a ",
+                    "JSON array. Records are read as their keys."
                 ))
-                .short("a")
-                .long("array")
-                .value_name("ARRAY")
+                .short("i")
+                .long("input")
                 .takes_value(true)
-                .possible_values(&["shuffle", "random", "count", "reverse"])
-                .default_value("shuffle"),
+                .value_name("INPUT"),
         )
         .arg(
-            Arg::with_name("size")
-                .help("Size of the test array.")
-                .short("s")
-                .long("size")
+            Arg::with_name("output")
+                .help(concat!(
+                    "Write the array to a file after the operation, one element per line. If ",
+                    "OUTPUT is `-` it goes to stdout and the report goes to stderr."
+                ))
+                .long("output")
                 .takes_value(true)
-                .value_name("SIZE")
-                .default_value("40"),
+                .value_name("OUTPUT"),
         )
         .arg(
-            Arg::with_name("run_size")
-                .help("Size of the window for a running sort.")
-                .short("r")
-                .long("run-size")
+            Arg::with_name("trace")
+                .help(concat!(
+                    "Write every c-heap operation, with its markers before and after, and every ",
+                    "swap to TRACE as JSON lines, for cheap replay. Only operations that sort the ",
+                    "array with c-heaps can be traced."
+                ))
+                .long("trace")
                 .takes_value(true)
-                .value_name("RUN_SIZE")
-                .default_value("16"),
+                .value_name("TRACE"),
         )
         .arg(
             Arg::with_name("count")
@@ -1172,46 +1552,101 @@ fn main() {
         )
         .get_matches();
 
-    let op: Op = Op::from_str(matches.value_of("op"));
-    let ac: ArrayCon = ArrayCon::from_str(matches.value_of("array"));
-    match op {
-        Op::Unknown => return usage("Unknown or unspecified operation."),
-        _ => (),
+    if let Some(sm) = matches.subcommand_matches("sweep") {
+        let w = match Workload::from_matches(sm) {
+            Ok(w) => w,
+            Err(e) => usage(&e),
+        };
+        let format = TableFormat::from_str(sm.value_of("format"));
+        let min = parse_int(sm.value_of("min"), 4).max(1);
+        let max = parse_int(sm.value_of("max"), 32768);
+        let reps = parse_int(sm.value_of("reps"), 1).max(1);
+        let min = match min.checked_next_power_of_two() {
+            Some(size) if size <= max => size,
+            Some(size) => usage(&format!(
+                "--min {} rounds up to {}, which is past --max {}",
+                min, size, max
+            )),
+            None => usage(&format!(
+                "--min {} is too big to round up to a power of two",
+                min
+            )),
+        };
+        if let Err(e) = check_len(&w.op, &w.params, min) {
+            usage(&e);
+        }
+        if let Err(e) = sweep(w, min, max, reps, &format) {
+            failure(&e);
+        }
+        return;
     }
-    let n_len = parse_int(matches.value_of("size"), 40);
-    let run_size = parse_int(matches.value_of("run_size"), 16);
 
-    let mut n: Vec<i32> = ac.make_array(n_len);
-
-    let mut out = object! {
-        "op"    => op.to_string(),
-        "array" => ac.to_string(),
-        "num_elems"     => n_len,
-    };
-
-    let now = SystemTime::now();
-    if matches.is_present("count") {
-        let mut cnt = RealCounter {
-            swaps: 0,
-            compares: 0,
+    if let Some(cm) = matches.subcommand_matches("compare") {
+        let w = match Workload::from_matches(cm) {
+            Ok(w) => w,
+            Err(e) => usage(&e),
         };
-        op.run(&mut n, run_size, &mut cnt);
-        cnt.copy_to(&mut out);
-    } else {
-        op.run(&mut n, run_size, &mut DummyCounter {});
+        let ops: Vec<&str> = cm.values_of("op").map_or(Vec::new(), |ops| ops.collect());
+        for &name in &ops {
+            if let Err(e) = check_len(&Op::from_str(Some(name)), &w.params, w.ap.size) {
+                usage(&e);
+            }
+        }
+        let format = TableFormat::from_str(cm.value_of("format"));
+        if let Err(e) = compare(w, &ops, &format) {
+            failure(&e);
+        }
+        return;
     }
-    match now.elapsed() {
-        Ok(elapsed) => {
-            out["elapsed"] = elapsed.as_secs_f64().into();
+
+    if let Some(rm) = matches.subcommand_matches("replay") {
+        match replay_trace(rm.value_of("trace").unwrap_or("-")) {
+            Ok(out) => println!("{}", out),
+            Err(e) => failure(&e),
         }
-        Err(_) => (),
+        return;
+    }
+
+    let mut w = match Workload::from_matches(&matches) {
+        Ok(w) => w,
+        Err(e) => usage(&e),
     };
+    let count = matches.is_present("count");
 
-    if op.does_sort() {
-        out["is_sorted"] = JsonValue::Boolean(is_sorted(&n, 0, n_len));
+    let input_path = matches.value_of("input");
+    // The length of an input array isn't known until it's read, so it's checked as it's run.
+    if input_path.is_none() {
+        if let Err(e) = check_len(&w.op, &w.params, w.ap.size) {
+            usage(&e);
+        }
     }
-    if out.write(&mut io::stdout()).is_err() {
-        return failure("Can't write to stdout");
+    let input = match input_path.map(read_input) {
+        Some(Ok(text)) => Some(text),
+        Some(Err(e)) => failure(&e),
+        None => None,
+    };
+    if input.is_some() {
+        w.ac = ArrayCon::Input;
+    }
+    let files = Files {
+        input,
+        output: matches.value_of("output").map(str::to_string),
+        trace: matches.value_of("trace").map(str::to_string),
+    };
+    let mut out = match w.run(count, &files) {
+        Ok(out) => out,
+        Err(e) => failure(&e),
+    };
+    if let Some(path) = input_path {
+        out["input"] = path.into();
+    }
+    // The sorted array has stdout to itself when it's written there.
+    if let Some("-") = files.output.as_deref() {
+        eprintln!("{}", out);
+    } else {
+        if out.write(&mut io::stdout()).is_err() {
+            failure("Can't write to stdout");
+        }
+        println!();
     }
-    println!("");
 }
@@ -0,0 +1,241 @@
+use crate::cheap::Cheap;
+use crate::counter::{CheapOp, Counter, DummyCounter, Sift};
+use crate::order::{NaturalOrder, Order, Reversed};
+use json::JsonValue;
+use std::collections::VecDeque;
+use std::fmt;
+
+/*
+ * Passes everything on to the window's counter, and follows each swap so the window knows
+ * where every element sits. `tags[i]` is the arrival number of the element in slot `i`, and
+ * `slots[t - oldest]` is the slot holding the element that arrived `t`th.
+ */
+#[derive(Debug)]
+struct Tracked<'t, C> {
+    cnt: &'t mut C,
+    tags: &'t mut [u64],
+    slots: &'t mut VecDeque<usize>,
+    oldest: u64,
+}
+
+impl<'t, C: Counter> Counter for Tracked<'t, C> {
+    fn count_compare(&mut self) {
+        self.cnt.count_compare();
+    }
+    fn count_swap(&mut self) {
+        self.cnt.count_swap();
+    }
+    fn count_move(&mut self) {
+        self.cnt.count_move();
+    }
+    fn count_swap_at(&mut self, i: usize, j: usize) {
+        self.tags.swap(i, j);
+        self.slots[(self.tags[i] - self.oldest) as usize] = i;
+        self.slots[(self.tags[j] - self.oldest) as usize] = j;
+        self.cnt.count_swap_at(i, j);
+    }
+    fn copy_to(&self, tgt: &mut JsonValue) {
+        self.cnt.copy_to(tgt);
+    }
+    fn enter(&mut self, op: CheapOp, params: (usize, usize, usize), arg: Option<usize>) {
+        self.cnt.enter(op, params, arg);
+    }
+    fn leave(&mut self, op: CheapOp, params: (usize, usize, usize)) {
+        self.cnt.leave(op, params);
+    }
+    fn count_recenter(&mut self, sifted: usize) {
+        self.cnt.count_recenter(sifted);
+    }
+    fn count_sift(&mut self, sift: Sift, levels: usize) {
+        self.cnt.count_sift(sift, levels);
+    }
+}
+
+/**
+ * A running median over a window of elements that arrive and leave in order.
+ *
+ * Two c-heaps sit side by side in one buffer: the low half keeps its greatest value at its
+ * center, and the high half keeps its least value at its center.
+ *
+ * ```text
+ *      [x, x, L, x][x, H, x, x]
+ *       low half    high half
+ * ```
+ *
+ * The low half holds as many elements as the high half, or one more, so the median is at `L`.
+ * Elements cross between the halves through the slots where they meet: popping the low half to
+ * the right leaves its best value where the high half can push it from the left, and the
+ * reverse.
+ *
+ * The window follows every swap to keep track of which slot holds which element, so
+ * `evict_oldest` finds the oldest element without searching for it.
+ */
+#[derive(Debug)]
+pub struct SlidingMedian<E: fmt::Debug, O = NaturalOrder, C: Counter + fmt::Debug = DummyCounter> {
+    buf: Vec<E>,
+    tags: Vec<u64>,
+    slots: VecDeque<usize>,
+    low_c: usize,
+    low_hi: usize,
+    high_lo: usize,
+    high_c: usize,
+    high_hi: usize,
+    seq: u64,
+    ord: O,
+    cnt: C,
+}
+
+impl<E: PartialOrd + fmt::Debug> SlidingMedian<E> {
+    /// Construct an empty window ordered by `PartialOrd`.
+    pub fn new() -> Self {
+        SlidingMedian::with_parts(0, NaturalOrder, DummyCounter {})
+    }
+}
+
+impl<E: PartialOrd + fmt::Debug> Default for SlidingMedian<E> {
+    fn default() -> Self {
+        SlidingMedian::new()
+    }
+}
+
+impl<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug> SlidingMedian<E, O, C> {
+    /// Construct an empty window with room for `capacity` elements, ranked by `ord` and
+    /// reporting to `cnt`.
+    pub fn with_parts(capacity: usize, ord: O, cnt: C) -> Self {
+        SlidingMedian {
+            buf: Vec::with_capacity(capacity),
+            tags: Vec::with_capacity(capacity),
+            slots: VecDeque::with_capacity(capacity),
+            low_c: 0,
+            low_hi: 0,
+            high_lo: 0,
+            high_c: 0,
+            high_hi: 0,
+            seq: 0,
+            ord,
+            cnt,
+        }
+    }
+
+    /// Number of elements in the window.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// The counter that has seen every compare and swap.
+    pub fn counter(&self) -> &C {
+        &self.cnt
+    }
+
+    /// The median, or the lower of the two middle elements when the window holds an even
+    /// number of them.
+    pub fn median(&self) -> Option<&E> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(&self.buf[self.low_c])
+        }
+    }
+
+    /// The two middle elements, lower first. They're the same element when the window holds
+    /// an odd number of them.
+    pub fn median_pair(&self) -> Option<(&E, &E)> {
+        let low = self.median()?;
+        if self.low_hi > self.buf.len() - self.low_hi {
+            Some((low, low))
+        } else {
+            Some((low, &self.buf[self.high_c]))
+        }
+    }
+
+    /// Add an element to the window as its newest.
+    pub fn push(&mut self, v: E) {
+        let i = self.buf.len();
+        self.buf.push(v);
+        self.tags.push(self.seq);
+        self.slots.push_back(i);
+        self.seq += 1;
+        // Swap the new element for the low half's greatest if that's greater, then hand
+        // whichever is left over to the high half.
+        self.with_low(|ch| ch.pushpop(i));
+        self.with_high(|ch| ch.push_right());
+        self.rebalance();
+    }
+
+    /// Remove the oldest element from the window, if any.
+    pub fn evict_oldest(&mut self) -> Option<E> {
+        let oldest = *self.slots.front()?;
+        if oldest < self.low_hi {
+            // The low half lets go of the slot next to the high half, which takes it in and
+            // then lets go of it at its far end.
+            self.with_low(|ch| ch.remove_right(oldest));
+            self.with_high(|ch| ch.push_left());
+            let oldest = self.slots[0];
+            self.with_high(|ch| ch.remove_right(oldest));
+        } else {
+            self.with_high(|ch| ch.remove_right(oldest));
+        }
+        self.tags.pop();
+        self.slots.pop_front();
+        let v = self.buf.pop()?;
+        self.rebalance();
+        Some(v)
+    }
+
+    // Move elements across the middle until the low half holds the same number as the high
+    // half, or one more.
+    fn rebalance(&mut self) {
+        let n = self.buf.len();
+        while self.low_hi < n - self.low_hi {
+            self.with_high(|ch| ch.pop_left());
+            self.with_low(|ch| ch.push_right());
+        }
+        while self.low_hi > n - self.low_hi + 1 {
+            self.with_low(|ch| ch.pop_right());
+            self.with_high(|ch| ch.push_left());
+        }
+    }
+
+    // Run a c-heap operation over the low half and save the markers it leaves behind.
+    fn with_low<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Cheap<E, Tracked<C>, Reversed<&mut O>>),
+    {
+        let mut cnt = Tracked {
+            cnt: &mut self.cnt,
+            tags: &mut self.tags,
+            slots: &mut self.slots,
+            oldest: self.seq - self.buf.len() as u64,
+        };
+        let mut ch = Cheap::new_range(&mut self.buf, 0, self.low_c, self.low_hi, &mut cnt)
+            .with_order(Reversed(&mut self.ord));
+        f(&mut ch);
+        let (_, c, hi) = ch.params();
+        self.low_c = c;
+        self.low_hi = hi;
+    }
+
+    // Run a c-heap operation over the high half and save the markers it leaves behind.
+    fn with_high<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Cheap<E, Tracked<C>, &mut O>),
+    {
+        let (lo, c, hi) = (self.high_lo, self.high_c, self.high_hi);
+        let mut cnt = Tracked {
+            cnt: &mut self.cnt,
+            tags: &mut self.tags,
+            slots: &mut self.slots,
+            oldest: self.seq - self.buf.len() as u64,
+        };
+        let mut ch = Cheap::new_range(&mut self.buf, lo, c, hi, &mut cnt).with_order(&mut self.ord);
+        f(&mut ch);
+        let (lo, c, hi) = ch.params();
+        self.high_lo = lo;
+        self.high_c = c;
+        self.high_hi = hi;
+    }
+}
@@ -0,0 +1,605 @@
+use crate::cheap::Cheap;
+use crate::counter::{CheapOp, Counter, Sift};
+use crate::order::{ByKey, ByOrdering, NaturalOrder, Order};
+use crate::sort::is_sorted_with;
+use crate::stable::apply_permutation;
+use json::JsonValue;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter;
+
+/*
+ * The markers of one run's c-heap. A run that hasn't taken in any value from another run is
+ * still sorted, which is a valid c-heap centered at its low end.
+ */
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    lo: usize,
+    c: usize,
+    hi: usize,
+    sorted: bool,
+}
+
+/*
+ * Ranks runs by the best value each one holds at its center.
+ */
+struct HeadOrder<'h, E, O> {
+    a: &'h [E],
+    runs: &'h [Run],
+    ord: &'h mut O,
+}
+
+impl<'h, E, O: Order<E>> Order<usize> for HeadOrder<'h, E, O> {
+    #[inline]
+    fn le(&mut self, i: &usize, j: &usize) -> bool {
+        self.ord
+            .le(&self.a[self.runs[*i].c], &self.a[self.runs[*j].c])
+    }
+    #[inline]
+    fn lt(&mut self, i: &usize, j: &usize) -> bool {
+        self.ord
+            .lt(&self.a[self.runs[*i].c], &self.a[self.runs[*j].c])
+    }
+}
+
+// Run a c-heap operation over one run and save the markers it leaves behind.
+fn with_run<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug, F>(
+    a: &mut [E],
+    run: &mut Run,
+    ord: &mut O,
+    cnt: &mut C,
+    f: F,
+) where
+    F: FnOnce(&mut Cheap<E, C, &mut O>),
+{
+    let mut ch = Cheap::new_range(a, run.lo, run.c, run.hi, cnt).with_order(ord);
+    f(&mut ch);
+    let (lo, c, hi) = ch.params();
+    run.lo = lo;
+    run.c = c;
+    run.hi = hi;
+}
+
+/*
+ * Passes everything on to the real counter, and follows each swap in the heap of run heads.
+ * `ids[i]` is the run in slot `i` of the heap, and `slots[r]` is the slot holding run `r`.
+ */
+#[derive(Debug)]
+struct Placed<'t, C> {
+    cnt: &'t mut C,
+    ids: &'t mut [usize],
+    slots: &'t mut [usize],
+}
+
+impl<'t, C: Counter> Counter for Placed<'t, C> {
+    fn count_compare(&mut self) {
+        self.cnt.count_compare();
+    }
+    fn count_swap(&mut self) {
+        self.cnt.count_swap();
+    }
+    fn count_move(&mut self) {
+        self.cnt.count_move();
+    }
+    fn count_swap_at(&mut self, i: usize, j: usize) {
+        self.ids.swap(i, j);
+        self.slots[self.ids[i]] = i;
+        self.slots[self.ids[j]] = j;
+        self.cnt.count_swap_at(i, j);
+    }
+    fn copy_to(&self, tgt: &mut JsonValue) {
+        self.cnt.copy_to(tgt);
+    }
+    fn enter(&mut self, op: CheapOp, params: (usize, usize, usize), arg: Option<usize>) {
+        self.cnt.enter(op, params, arg);
+    }
+    fn leave(&mut self, op: CheapOp, params: (usize, usize, usize)) {
+        self.cnt.leave(op, params);
+    }
+    fn count_recenter(&mut self, sifted: usize) {
+        self.cnt.count_recenter(sifted);
+    }
+    fn count_sift(&mut self, sift: Sift, levels: usize) {
+        self.cnt.count_sift(sift, levels);
+    }
+}
+
+/*
+ * The c-heap of the heads of every run but the first, and the slot each run sits in, so the
+ * next run can be taken out without searching for it.
+ */
+struct Heads {
+    heap: Vec<usize>,
+    ids: Vec<usize>,
+    slots: Vec<usize>,
+    params: (usize, usize, usize),
+}
+
+impl Heads {
+    // Runs 1..k in order, which isn't a heap until it's recentered. Run 0 has no slot.
+    fn new(k: usize) -> Self {
+        let heap: Vec<usize> = (1..k).collect();
+        Heads {
+            ids: heap.clone(),
+            slots: iter::once(usize::MAX).chain(0..k - 1).collect(),
+            params: (0, 0, k - 1),
+            heap,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.params.0 == self.params.2
+    }
+
+    // The run with the best head.
+    fn best(&self) -> usize {
+        self.heap[self.params.1]
+    }
+
+    // Run a c-heap operation over the heads and save the markers it leaves behind.
+    fn with<E, O: Order<E>, C: Counter + fmt::Debug, F>(
+        &mut self,
+        a: &[E],
+        runs: &[Run],
+        ord: &mut O,
+        cnt: &mut C,
+        f: F,
+    ) where
+        F: FnOnce(&mut Cheap<usize, Placed<C>, HeadOrder<E, O>>),
+    {
+        let (lo, c, hi) = self.params;
+        let mut placed = Placed {
+            cnt,
+            ids: &mut self.ids,
+            slots: &mut self.slots,
+        };
+        let mut ch = Cheap::new_range(&mut self.heap, lo, c, hi, &mut placed)
+            .with_order(HeadOrder { a, runs, ord });
+        f(&mut ch);
+        self.params = ch.params();
+    }
+}
+
+/**
+ * Merge adjacent sorted runs in place, in one pass: `a[..bounds[0]]`, `a[bounds[0]..bounds[1]]`,
+ * and so on up to `a[bounds[k - 1]..]`.
+ *
+ * Every run is treated as a c-heap; a sorted run already is one, centered at its low end. The
+ * output slot is always the low end of the first run that isn't used up, and that run's best
+ * value is weighed against the best of the other runs, which are kept in a c-heap of run heads:
+ *
+ * ```text
+ *      output   first      run 2      run 3
+ *      . . . [o, x, x] [x, x, x, x] [x, x, x]
+ *            ^ix
+ * ```
+ *
+ * The first run pops its best value into the output slot. If another run's best is better, that
+ * run then swaps it for the value in the output slot with `poppush`. A run only gets worse as a
+ * candidate, so the heap of run heads just pops it and pushes it back in.
+ *
+ * The elements are merged in place, but the bookkeeping takes O(k) extra space for `k` runs: a
+ * few words per run for its markers and its slot in the heap of run heads.
+ *
+ * Panics if `bounds` is not ascending or reaches past the end of `a`.
+ */
+pub fn merge_runs<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    bounds: &[usize],
+    cnt: &mut C,
+) {
+    merge_runs_with(a, bounds, NaturalOrder, cnt)
+}
+
+/// Merge adjacent runs sorted by `ord`; see `merge_runs`.
+pub fn merge_runs_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    bounds: &[usize],
+    mut ord: O,
+    cnt: &mut C,
+) {
+    let a_len = a.len();
+    let mut runs = Vec::with_capacity(bounds.len() + 1);
+    let mut start = 0;
+    for &end in bounds.iter().chain(iter::once(&a_len)) {
+        assert!(
+            start <= end && end <= a_len,
+            "merge_runs: bounds must ascend within the array"
+        );
+        debug_assert!(
+            is_sorted_with(a, start, end, &mut ord),
+            "merge_runs(pre): run {}..{} not sorted",
+            start,
+            end
+        );
+        if start < end {
+            runs.push(Run {
+                lo: start,
+                c: start,
+                hi: end,
+                sorted: true,
+            });
+        }
+        start = end;
+    }
+    if runs.len() < 2 {
+        return;
+    }
+
+    let mut heads = Heads::new(runs.len());
+    heads.with(a, &runs, &mut ord, cnt, |ch| ch.recenter());
+
+    let mut f = 0;
+    for ix in 0..a_len {
+        debug_assert_eq!(runs[f].lo, ix, "merge_runs: output slot outside first run");
+        if heads.is_empty() && runs[f].sorted {
+            // Only the first run is left, and it's still in order.
+            break;
+        }
+        let other = if heads.is_empty() {
+            None
+        } else {
+            let b = heads.best();
+            cnt.count_compare();
+            if ord.le(&a[runs[f].c], &a[runs[b].c]) {
+                None
+            } else {
+                Some(b)
+            }
+        };
+
+        if runs[f].sorted {
+            runs[f].lo += 1;
+            runs[f].c += 1;
+        } else {
+            with_run(a, &mut runs[f], &mut ord, cnt, |ch| ch.pop_left());
+        }
+        if let Some(b) = other {
+            // Run b's best is about to get worse, so take it off the heads while that happens.
+            heads.with(a, &runs, &mut ord, cnt, |ch| ch.pop_left());
+            with_run(a, &mut runs[b], &mut ord, cnt, |ch| ch.poppush(ix));
+            runs[b].sorted = false;
+            heads.with(a, &runs, &mut ord, cnt, |ch| ch.push_left());
+        }
+
+        if runs[f].lo == runs[f].hi {
+            f += 1;
+            if f == runs.len() {
+                break;
+            }
+            let pos = heads.slots[f];
+            heads.with(a, &runs, &mut ord, cnt, |ch| ch.remove_left(pos));
+        }
+    }
+}
+
+/// Merge adjacent runs sorted by `compare`; see `merge_runs`.
+pub fn merge_runs_by<E: fmt::Debug, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    bounds: &[usize],
+    compare: F,
+    cnt: &mut C,
+) {
+    merge_runs_with(a, bounds, ByOrdering(compare), cnt)
+}
+
+/// Merge adjacent runs sorted by the keys `key` extracts; see `merge_runs`.
+pub fn merge_runs_by_key<E: fmt::Debug, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    bounds: &[usize],
+    key: F,
+    cnt: &mut C,
+) {
+    merge_runs_with(a, bounds, ByKey(key), cnt)
+}
+
+/**
+ * The in-place merges `merge_sort` and `bottom_up_merge_sort` can be given, by name.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// `Cheap::merge`, which carries displaced values along in a c-heap.
+    Cheap,
+    /// `sym_merge`, Kim and Kutzner's SymMerge.
+    SymMerge,
+    /// `block_swap_merge`, the classic recursive merge by rotation.
+    BlockSwap,
+    /// `buffered_merge`, which plans the merge in O(n) scratch space.
+    Buffered,
+}
+
+impl MergeStrategy {
+    /// Every strategy, in the order the CLI lists them.
+    pub const ALL: [MergeStrategy; 4] = [
+        MergeStrategy::Cheap,
+        MergeStrategy::SymMerge,
+        MergeStrategy::BlockSwap,
+        MergeStrategy::Buffered,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MergeStrategy::Cheap => "cheap",
+            MergeStrategy::SymMerge => "sym_merge",
+            MergeStrategy::BlockSwap => "block_swap",
+            MergeStrategy::Buffered => "buffered",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        MergeStrategy::ALL
+            .iter()
+            .copied()
+            .find(|s| s.name() == name)
+    }
+
+    /// The merge, in the form `merge_sort` takes.
+    pub fn merge_fn<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+        self,
+    ) -> fn(&mut [E], usize, usize, usize, &mut C) {
+        match self {
+            MergeStrategy::Cheap => Cheap::merge,
+            MergeStrategy::SymMerge => sym_merge,
+            MergeStrategy::BlockSwap => block_swap_merge,
+            MergeStrategy::Buffered => buffered_merge,
+        }
+    }
+
+    /// The merge, in the form `merge_sort_with` takes.
+    pub fn merge_with_fn<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+        self,
+    ) -> fn(&mut [E], usize, usize, usize, &mut O, &mut C) {
+        match self {
+            MergeStrategy::Cheap => Cheap::merge_with,
+            MergeStrategy::SymMerge => sym_merge_with,
+            MergeStrategy::BlockSwap => block_swap_merge_with,
+            MergeStrategy::Buffered => buffered_merge_with,
+        }
+    }
+}
+
+impl fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/*
+ * Swap the `len` elements at `i` with the `len` elements at `j`. The blocks mustn't overlap.
+ */
+fn swap_blocks<E, C: Counter>(a: &mut [E], i: usize, j: usize, len: usize, cnt: &mut C) {
+    for k in 0..len {
+        cnt.count_swap_at(i + k, j + k);
+        a.swap(i + k, j + k);
+    }
+}
+
+/*
+ * Rotate `a[lo..hi]` so `a[md..hi]` comes before `a[lo..md]`, by repeatedly swapping the
+ * shorter side into place (Gries and Mills). Takes at most `hi - lo` swaps.
+ */
+fn rotate<E, C: Counter>(a: &mut [E], lo: usize, md: usize, hi: usize, cnt: &mut C) {
+    let (mut i, mut j) = (md - lo, hi - md);
+    if i == 0 || j == 0 {
+        return;
+    }
+    while i != j {
+        if i > j {
+            swap_blocks(a, md - i, md, j, cnt);
+            i -= j;
+        } else {
+            swap_blocks(a, md - i, md + j - i, i, cnt);
+            j -= i;
+        }
+    }
+    swap_blocks(a, md - i, md, i, cnt);
+}
+
+#[inline]
+fn less<E, O: Order<E>, C: Counter>(a: &[E], i: usize, j: usize, ord: &mut O, cnt: &mut C) -> bool {
+    cnt.count_compare();
+    ord.lt(&a[i], &a[j])
+}
+
+/**
+ * Given lo:md is sorted and md:hi is sorted, merge them with SymMerge (Kim and Kutzner, 2004).
+ *
+ * The split point is found by a binary search that's symmetric about the middle of `lo..hi`,
+ * the blocks either side of `md` are rotated past each other, and each half is merged in turn.
+ * Equal values keep their order. Takes O(log n) stack and no other extra memory.
+ */
+pub fn sym_merge<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    cnt: &mut C,
+) {
+    sym_merge_with(a, lo, md, hi, &mut NaturalOrder, cnt)
+}
+
+/// SymMerge the sorted runs lo:md and md:hi as ranked by `ord`; see `sym_merge`.
+pub fn sym_merge_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    ord: &mut O,
+    cnt: &mut C,
+) {
+    if lo == md || md == hi {
+        return;
+    }
+    if md - lo == 1 {
+        // Move a[lo] up to the first value in md..hi that isn't less than it.
+        let (mut i, mut j) = (md, hi);
+        while i < j {
+            let h = (i + j) / 2;
+            if less(a, h, lo, ord, cnt) {
+                i = h + 1;
+            } else {
+                j = h;
+            }
+        }
+        for k in lo..i - 1 {
+            cnt.count_swap_at(k, k + 1);
+            a.swap(k, k + 1);
+        }
+        return;
+    }
+    if hi - md == 1 {
+        // Move a[md] down past every value in lo..md that's greater than it.
+        let (mut i, mut j) = (lo, md);
+        while i < j {
+            let h = (i + j) / 2;
+            if !less(a, md, h, ord, cnt) {
+                i = h + 1;
+            } else {
+                j = h;
+            }
+        }
+        for k in (i + 1..=md).rev() {
+            cnt.count_swap_at(k, k - 1);
+            a.swap(k, k - 1);
+        }
+        return;
+    }
+    let mid = (lo + hi) / 2;
+    let n = mid + md;
+    let (mut start, mut r) = if md > mid { (n - hi, mid) } else { (lo, md) };
+    let p = n - 1;
+    while start < r {
+        let c = (start + r) / 2;
+        if !less(a, p - c, c, ord, cnt) {
+            start = c + 1;
+        } else {
+            r = c;
+        }
+    }
+    let end = n - start;
+    if start < md && md < end {
+        rotate(a, start, md, end, cnt);
+    }
+    if lo < start && start < mid {
+        sym_merge_with(a, lo, start, mid, ord, cnt);
+    }
+    if mid < end && end < hi {
+        sym_merge_with(a, mid, end, hi, ord, cnt);
+    }
+}
+
+/**
+ * Given lo:md is sorted and md:hi is sorted, merge them by block swaps, the classic way: cut
+ * the longer run in half, binary search the shorter run for where that cut belongs, rotate the
+ * two middle blocks past each other, and merge each side.
+ *
+ * ```text
+ *      [ A1 | A2 ][ B1 | B2 ]  ->  [ A1 | B1 ][ A2 | B2 ]
+ * ```
+ *
+ * Equal values keep their order. Takes O(log n) stack and no other extra memory.
+ */
+pub fn block_swap_merge<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    cnt: &mut C,
+) {
+    block_swap_merge_with(a, lo, md, hi, &mut NaturalOrder, cnt)
+}
+
+/// Block-swap merge the sorted runs lo:md and md:hi as ranked by `ord`; see `block_swap_merge`.
+pub fn block_swap_merge_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    ord: &mut O,
+    cnt: &mut C,
+) {
+    let (len1, len2) = (md - lo, hi - md);
+    if len1 == 0 || len2 == 0 {
+        return;
+    }
+    if len1 + len2 == 2 {
+        if less(a, md, lo, ord, cnt) {
+            cnt.count_swap_at(lo, md);
+            a.swap(lo, md);
+        }
+        return;
+    }
+    let (cut1, cut2) = if len1 > len2 {
+        // The first value in md..hi that isn't less than a[cut1].
+        let cut1 = lo + len1 / 2;
+        let (mut i, mut j) = (md, hi);
+        while i < j {
+            let h = (i + j) / 2;
+            if less(a, h, cut1, ord, cnt) {
+                i = h + 1;
+            } else {
+                j = h;
+            }
+        }
+        (cut1, i)
+    } else {
+        // The first value in lo..md that's greater than a[cut2].
+        let cut2 = md + len2 / 2;
+        let (mut i, mut j) = (lo, md);
+        while i < j {
+            let h = (i + j) / 2;
+            if less(a, cut2, h, ord, cnt) {
+                j = h;
+            } else {
+                i = h + 1;
+            }
+        }
+        (i, cut2)
+    };
+    rotate(a, cut1, md, cut2, cnt);
+    let new_md = cut1 + (cut2 - md);
+    block_swap_merge_with(a, lo, cut1, new_md, ord, cnt);
+    block_swap_merge_with(a, new_md, cut2, hi, ord, cnt);
+}
+
+/**
+ * Given lo:md is sorted and md:hi is sorted, merge them as a baseline for the in-place merges.
+ *
+ * The merge is planned the textbook way, into `hi - lo` words of scratch space that record
+ * where each value comes from, and then the values are swapped into place. Equal values keep
+ * their order. Takes at most `hi - lo - 1` compares and `hi - lo` swaps.
+ */
+pub fn buffered_merge<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    cnt: &mut C,
+) {
+    buffered_merge_with(a, lo, md, hi, &mut NaturalOrder, cnt)
+}
+
+/// Merge the sorted runs lo:md and md:hi as ranked by `ord`; see `buffered_merge`.
+pub fn buffered_merge_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    ord: &mut O,
+    cnt: &mut C,
+) {
+    let mut idx = Vec::with_capacity(hi - lo);
+    let (mut i, mut j) = (lo, md);
+    while i < md && j < hi {
+        if less(a, j, i, ord, cnt) {
+            idx.push(j - lo);
+            j += 1;
+        } else {
+            idx.push(i - lo);
+            i += 1;
+        }
+    }
+    idx.extend(i - lo..md - lo);
+    idx.extend(j - lo..hi - lo);
+    apply_permutation(a, lo, &mut idx, cnt);
+}
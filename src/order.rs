@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+
+/**
+ * Decides which of two elements is "better", i.e. belongs nearer the front of a sorted run.
+ *
+ * `le` is the "better than or equal" test used to maintain the heap invariant, and `lt` is the
+ * strict test used to pick between candidates during a merge. Implementations must agree with
+ * each other: `lt(a, b)` implies `le(a, b)`.
+ *
+ * Implementations don't count anything; the c-heap reports each call to its `Counter`.
+ */
+pub trait Order<E: ?Sized> {
+    fn le(&mut self, a: &E, b: &E) -> bool;
+    fn lt(&mut self, a: &E, b: &E) -> bool;
+}
+
+/// Order elements by `PartialOrd`, using `<=` and `<` directly.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NaturalOrder;
+
+impl<E: PartialOrd + ?Sized> Order<E> for NaturalOrder {
+    #[inline]
+    fn le(&mut self, a: &E, b: &E) -> bool {
+        a <= b
+    }
+    #[inline]
+    fn lt(&mut self, a: &E, b: &E) -> bool {
+        a < b
+    }
+}
+
+/// Order elements by a comparator, as `slice::sort_by` does.
+#[derive(Debug, Clone, Copy)]
+pub struct ByOrdering<F>(pub F);
+
+impl<E: ?Sized, F: FnMut(&E, &E) -> Ordering> Order<E> for ByOrdering<F> {
+    #[inline]
+    fn le(&mut self, a: &E, b: &E) -> bool {
+        (self.0)(a, b) != Ordering::Greater
+    }
+    #[inline]
+    fn lt(&mut self, a: &E, b: &E) -> bool {
+        (self.0)(a, b) == Ordering::Less
+    }
+}
+
+/// Order elements by a computed key, as `slice::sort_by_key` does.
+#[derive(Debug, Clone, Copy)]
+pub struct ByKey<F>(pub F);
+
+impl<E: ?Sized, K: Ord, F: FnMut(&E) -> K> Order<E> for ByKey<F> {
+    #[inline]
+    fn le(&mut self, a: &E, b: &E) -> bool {
+        (self.0)(a) <= (self.0)(b)
+    }
+    #[inline]
+    fn lt(&mut self, a: &E, b: &E) -> bool {
+        (self.0)(a) < (self.0)(b)
+    }
+}
+
+impl<E: ?Sized, O: Order<E> + ?Sized> Order<E> for &mut O {
+    #[inline]
+    fn le(&mut self, a: &E, b: &E) -> bool {
+        (**self).le(a, b)
+    }
+    #[inline]
+    fn lt(&mut self, a: &E, b: &E) -> bool {
+        (**self).lt(a, b)
+    }
+}
+
+/// Reverse another order, so the worst elements rank first.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Reversed<O>(pub O);
+
+impl<E: ?Sized, O: Order<E>> Order<E> for Reversed<O> {
+    #[inline]
+    fn le(&mut self, a: &E, b: &E) -> bool {
+        self.0.le(b, a)
+    }
+    #[inline]
+    fn lt(&mut self, a: &E, b: &E) -> bool {
+        self.0.lt(b, a)
+    }
+}
+
+/**
+ * A total order for floating point values, so they can be sorted and heaped safely.
+ *
+ * `PartialOrd` says nothing about NaN: every comparison with it is false, so `<=` and `<` stop
+ * agreeing with each other and a NaN can break the heap invariant without anything noticing.
+ * The total order puts every NaN after +inf, whatever its sign or payload, and treats all NaNs
+ * as equal. Other values keep their usual order, except that -0.0 comes before +0.0.
+ */
+pub trait TotalCmp {
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! total_cmp_float {
+    ($($t:ty), *) => {
+        $(
+            impl TotalCmp for $t {
+                #[inline]
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    match (self.is_nan(), other.is_nan()) {
+                        (false, false) => <$t>::total_cmp(self, other),
+                        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+total_cmp_float!(f32, f64);
+
+/// Order floating point elements by `TotalCmp`, so NaNs sort last.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TotalOrder;
+
+impl<E: TotalCmp + ?Sized> Order<E> for TotalOrder {
+    #[inline]
+    fn le(&mut self, a: &E, b: &E) -> bool {
+        a.total_cmp(b) != Ordering::Greater
+    }
+    #[inline]
+    fn lt(&mut self, a: &E, b: &E) -> bool {
+        a.total_cmp(b) == Ordering::Less
+    }
+}
+
+/**
+ * Wraps a floating point value so it's `Ord` by `TotalCmp`, for use with the plain entry points
+ * or anywhere else an `Ord` type is wanted.
+ */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TotalOrd<T>(pub T);
+
+impl<T: TotalCmp> PartialEq for TotalOrd<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.total_cmp(&other.0) == Ordering::Equal
+    }
+}
+
+impl<T: TotalCmp> Eq for TotalOrd<T> {}
+
+impl<T: TotalCmp> PartialOrd for TotalOrd<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: TotalCmp> Ord for TotalOrd<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
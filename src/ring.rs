@@ -0,0 +1,655 @@
+use crate::cheap::{get_left_child, get_parent, get_recenter_limit, get_right_child};
+use crate::counter::{CheapOp, Counter, Sift};
+use crate::error::CheapError;
+use crate::order::{NaturalOrder, Order};
+use std::fmt;
+
+/*
+ * The ring keeps its heap arithmetic in coordinates relative to lo, so the heap always spans
+ * 0..len with the center at c, and the usual child and parent rules apply unchanged. A relative
+ * index r lives in slot (lo + r) % a.len().
+ */
+
+/*
+ * The left child of physical slot x, for a ring of n slots starting at lo and centered at rc.
+ * Uses usize::MAX for known out of bounds, like `get_left_child`.
+ */
+#[inline]
+fn get_left_child_wrapped(x: usize, lo: usize, rc: usize, n: usize) -> usize {
+    wrap_out(get_left_child(unwrap_in(x, lo, n), rc), lo, n)
+}
+
+/*
+ * The right child of physical slot x, as `get_left_child_wrapped`.
+ */
+#[inline]
+fn get_right_child_wrapped(x: usize, lo: usize, rc: usize, n: usize) -> usize {
+    wrap_out(get_right_child(unwrap_in(x, lo, n), rc), lo, n)
+}
+
+/*
+ * The parent of physical slot x, as `get_left_child_wrapped`.
+ */
+#[inline]
+fn get_parent_wrapped(x: usize, lo: usize, rc: usize, n: usize) -> usize {
+    wrap_out(get_parent(unwrap_in(x, lo, n), rc), lo, n)
+}
+
+// Physical slot to an offset from lo.
+#[inline]
+fn unwrap_in(x: usize, lo: usize, n: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        (x + n - lo) % n
+    }
+}
+
+// Offset from lo to a physical slot. Offsets past the ring become usize::MAX so they can't
+// alias a slot inside it.
+#[inline]
+fn wrap_out(r: usize, lo: usize, n: usize) -> usize {
+    if r >= n {
+        usize::MAX
+    } else {
+        (lo + r) % n
+    }
+}
+
+/**
+ * A centered heap whose range may wrap around the end of its slice.
+ *
+ * This is `Cheap` with the slice treated as a ring: pushes, pops and slides that run off one
+ * end of the slice carry on at the other, so a heap can slide forever over a fixed buffer. The
+ * heap can hold up to `a.len()` elements, but sliding needs one free slot.
+ *
+ * Markers are physical slots. Since `hi` equals `lo` both when the ring is empty and when it is
+ * full, use `len` rather than comparing them.
+ *
+ * Operations report to the counter as `Cheap`'s do, with physical markers, so per-operation
+ * statistics cover them. `replay` can't follow a ring that wraps, though.
+ */
+pub struct RingCheap<'a, E: fmt::Debug, C: Counter + fmt::Debug, O = NaturalOrder> {
+    a: &'a mut [E],
+    lo: usize,
+    // Offset of the center from lo.
+    c: usize,
+    len: usize,
+    cnt: &'a mut C,
+    ord: O,
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug> RingCheap<'a, E, C> {
+    /// Construct an empty ring starting at slot 0.
+    pub fn new(a: &'a mut [E], cnt: &'a mut C) -> Self {
+        RingCheap {
+            a,
+            lo: 0,
+            c: 0,
+            len: 0,
+            cnt,
+            ord: NaturalOrder,
+        }
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O> RingCheap<'a, E, C, O> {
+    /// Replace the order used to rank elements.
+    ///
+    /// The heap is not revalidated; recenter it if it isn't empty.
+    pub fn with_order<O2>(self, ord: O2) -> RingCheap<'a, E, C, O2> {
+        RingCheap {
+            a: self.a,
+            lo: self.lo,
+            c: self.c,
+            len: self.len,
+            cnt: self.cnt,
+            ord,
+        }
+    }
+
+    /// Get the markers as physical slots `(lo, c, hi)`.
+    pub fn params(&self) -> (usize, usize, usize) {
+        (self.lo, self.phys(self.c), self.phys(self.len))
+    }
+
+    /// Number of elements inside the heap.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the heap holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of slots in the ring.
+    pub fn capacity(&self) -> usize {
+        self.a.len()
+    }
+
+    /// The best element, if the heap isn't empty.
+    pub fn peek(&self) -> Option<&E> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(&self.a[self.phys(self.c)])
+        }
+    }
+
+    /// The whole underlying slice, including the slots outside the heap.
+    pub fn as_slice(&self) -> &[E] {
+        self.a
+    }
+
+    /// Whether physical slot `i` is inside the heap.
+    pub fn contains(&self, i: usize) -> bool {
+        unwrap_in(i, self.lo, self.a.len()) < self.len
+    }
+
+    // The physical slot of offset r from lo.
+    #[inline]
+    fn phys(&self, r: usize) -> usize {
+        let n = self.a.len();
+        if n == 0 {
+            0
+        } else {
+            (self.lo + r) % n
+        }
+    }
+
+    /// Check only the range invariants, panicking if they fail.
+    pub fn check_range(&self) {
+        let n = self.a.len();
+        assert!(
+            self.len <= n && (n == 0 || self.lo < n),
+            "ring-state: markers outside ring"
+        );
+        assert!(
+            self.len == 0 && self.c == 0 || self.c < self.len,
+            "ring-state: center outside range"
+        );
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O: Order<E>> RingCheap<'a, E, C, O> {
+    #[inline]
+    fn swap(&mut self, i: usize, j: usize) {
+        self.cnt.count_swap_at(i, j);
+        self.a.swap(i, j);
+    }
+
+    // Check if a[i] is "better than" a[j].
+    #[inline]
+    fn bt(&mut self, i: usize, j: usize) -> bool {
+        self.cnt.count_compare();
+        self.ord.le(&self.a[i], &self.a[j])
+    }
+
+    // Run `f` as the operation `op` given the slot `arg`, so the counter can attribute its work.
+    #[inline]
+    fn op<T>(&mut self, op: CheapOp, arg: Option<usize>, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.cnt.enter(op, self.params(), arg);
+        let r = f(self);
+        self.cnt.leave(op, self.params());
+        r
+    }
+
+    /// Do a full check of the invariants, panicking if they fail.
+    pub fn check(&mut self) {
+        self.check_range();
+        assert!(self.is_valid(), "ring-state: heap invariant failed");
+    }
+
+    /// Check the heap invariant: every parent is better than its children.
+    pub fn is_valid(&mut self) -> bool {
+        let n = self.a.len();
+        let pc = self.phys(self.c);
+        for r in 0..self.len {
+            let i = self.phys(r);
+            if i != pc {
+                let p = get_parent_wrapped(i, self.lo, self.c, n);
+                if !self.ord.le(&self.a[p], &self.a[i]) {
+                    show_call!(
+                        self,
+                        "ring check: failed(a[{}]={:?} bt a[{}]={:?}, ",
+                        p,
+                        &self.a[p],
+                        i,
+                        &self.a[i]
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /**
+     * Rebuild the heap over the whole range around the current center. See `Cheap::recenter`.
+     */
+    pub fn recenter(&mut self) {
+        self.op(CheapOp::Recenter, None, |ring| {
+            dbg_show_call!(ring, "ring recenter-start(");
+            let (c, len) = (ring.c, ring.len);
+            let left = get_recenter_limit(0, c)..c;
+            let right = c..get_recenter_limit(len, c);
+            ring.cnt.count_recenter(left.len() + right.len());
+            for r in left {
+                ring.sift_out(r);
+            }
+            for r in right.rev() {
+                ring.sift_out(r);
+            }
+            #[cfg(debug_assertions)]
+            ring.check();
+            dbg_show_call!(ring, "ring recenter-end(");
+        })
+    }
+
+    // Move the element at offset r towards the leaves. See `Cheap::sift_out`.
+    fn sift_out(&mut self, r: usize) {
+        let (n, lo, c) = (self.a.len(), self.lo, self.c);
+        let mut x = self.phys(r);
+        let mut levels = 0;
+        loop {
+            let mut vio = None;
+            let ch1 = get_left_child_wrapped(x, lo, c, n);
+            if ch1 != usize::MAX && self.contains(ch1) && self.bt(ch1, x) {
+                vio = Some(ch1);
+            }
+            let ch2 = get_right_child_wrapped(x, lo, c, n);
+            if ch2 != usize::MAX && self.contains(ch2) && self.bt(ch2, x) {
+                match vio {
+                    Some(v) if !self.bt(ch2, v) => (),
+                    _ => vio = Some(ch2),
+                }
+            }
+            match vio {
+                Some(v) => {
+                    self.swap(x, v);
+                    x = v;
+                    levels += 1;
+                }
+                None => break,
+            }
+        }
+        self.cnt.count_sift(Sift::Out, levels);
+    }
+
+    // Move the element at offset r towards the center. See `Cheap::sift_in`.
+    fn sift_in(&mut self, r: usize) {
+        let (n, lo, c) = (self.a.len(), self.lo, self.c);
+        let pc = self.phys(c);
+        let mut x = self.phys(r);
+        let mut levels = 0;
+        while x != pc {
+            let p = get_parent_wrapped(x, lo, c, n);
+            if self.bt(x, p) {
+                self.swap(x, p);
+                x = p;
+                levels += 1;
+            } else {
+                break;
+            }
+        }
+        self.cnt.count_sift(Sift::In, levels);
+    }
+
+    /**
+     * Swap the best value into `lo` and shrink the range on the left. See `Cheap::pop_left`.
+     *
+     * Returns the slot holding the popped value.
+     */
+    pub fn pop_left(&mut self) -> usize {
+        self.try_pop_left().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `pop_left`, but returns an error instead of panicking.
+    pub fn try_pop_left(&mut self) -> Result<usize, CheapError> {
+        self.op(CheapOp::PopLeft, None, |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            if ring.is_empty() {
+                return Err(CheapError::Empty);
+            }
+            let out = ring.lo;
+            if ring.c == 0 {
+                ring.lo = ring.phys(1);
+                ring.len -= 1;
+                if ring.len > 0 {
+                    ring.c = ring.len - 1;
+                    ring.recenter();
+                }
+            } else {
+                ring.swap(ring.phys(ring.c), ring.lo);
+                ring.lo = ring.phys(1);
+                ring.len -= 1;
+                ring.c -= 1;
+                ring.sift_out(ring.c);
+            }
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(out)
+        })
+    }
+
+    /**
+     * Swap the best value into `hi - 1` and shrink the range on the right. See
+     * `Cheap::pop_right`.
+     *
+     * Returns the slot holding the popped value.
+     */
+    pub fn pop_right(&mut self) -> usize {
+        self.try_pop_right().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `pop_right`, but returns an error instead of panicking.
+    pub fn try_pop_right(&mut self) -> Result<usize, CheapError> {
+        self.op(CheapOp::PopRight, None, |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            if ring.is_empty() {
+                return Err(CheapError::Empty);
+            }
+            let hip = ring.len - 1;
+            let out = ring.phys(hip);
+            if hip == ring.c {
+                ring.c = 0;
+                ring.len = hip;
+                if hip > 0 {
+                    ring.recenter();
+                }
+            } else {
+                ring.swap(out, ring.phys(ring.c));
+                ring.len = hip;
+                ring.sift_out(ring.c);
+            }
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(out)
+        })
+    }
+
+    /**
+     * Absorb the value in the slot before `lo`, wrapping to the end of the slice if need be.
+     * See `Cheap::push_left`.
+     */
+    pub fn push_left(&mut self) {
+        self.try_push_left().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `push_left`, but returns an error instead of panicking.
+    pub fn try_push_left(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::PushLeft, None, |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            let n = ring.a.len();
+            if ring.len == n {
+                return Err(CheapError::Full);
+            }
+            ring.lo = (ring.lo + n - 1) % n;
+            if ring.len > 0 {
+                ring.c += 1;
+            }
+            ring.len += 1;
+            ring.sift_in(0);
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Absorb the value in the slot at `hi`, wrapping to the start of the slice if need be.
+     * See `Cheap::push_right`.
+     */
+    pub fn push_right(&mut self) {
+        self.try_push_right().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `push_right`, but returns an error instead of panicking.
+    pub fn try_push_right(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::PushRight, None, |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            if ring.len == ring.a.len() {
+                return Err(CheapError::Full);
+            }
+            ring.len += 1;
+            ring.sift_in(ring.len - 1);
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(())
+        })
+    }
+
+    /// Store `v` in the slot before `lo` and `push_left` it, returning the value it displaced.
+    pub fn insert_left(&mut self, v: E) -> E {
+        let n = self.a.len();
+        if self.len == n {
+            panic!("{}", CheapError::Full);
+        }
+        let old = std::mem::replace(&mut self.a[(self.lo + n - 1) % n], v);
+        self.push_left();
+        old
+    }
+
+    /// Store `v` in the slot at `hi` and `push_right` it, returning the value it displaced.
+    pub fn insert_right(&mut self, v: E) -> E {
+        if self.len == self.a.len() {
+            panic!("{}", CheapError::Full);
+        }
+        let hi = self.phys(self.len);
+        let old = std::mem::replace(&mut self.a[hi], v);
+        self.push_right();
+        old
+    }
+
+    /**
+     * Swap the value in slot `i` with the best value and preserve invariants. See
+     * `Cheap::poppush`.
+     */
+    pub fn poppush(&mut self, i: usize) {
+        self.try_poppush(i).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `poppush`, but returns an error instead of panicking.
+    pub fn try_poppush(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::Poppush, Some(i), |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            if ring.is_empty() {
+                return Err(CheapError::Empty);
+            }
+            if i >= ring.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            if ring.contains(i) {
+                return Err(CheapError::IndexInsideHeap);
+            }
+            let pc = ring.phys(ring.c);
+            ring.swap(i, pc);
+            ring.sift_out(ring.c);
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Swap the value in slot `i` with the best value, unless it is already better. See
+     * `Cheap::pushpop`.
+     */
+    pub fn pushpop(&mut self, i: usize) {
+        self.try_pushpop(i).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `pushpop`, but returns an error instead of panicking.
+    pub fn try_pushpop(&mut self, i: usize) -> Result<(), CheapError> {
+        self.op(CheapOp::Pushpop, Some(i), |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            if i >= ring.a.len() {
+                return Err(CheapError::OutOfBounds);
+            }
+            if ring.contains(i) {
+                return Err(CheapError::IndexInsideHeap);
+            }
+            if ring.is_empty() {
+                return Ok(());
+            }
+            let pc = ring.phys(ring.c);
+            if ring.bt(i, pc) {
+                return Ok(());
+            }
+            ring.swap(i, pc);
+            ring.sift_out(ring.c);
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Transfer the value at `hi` over to the left, moving the whole range one slot to the right
+     * and wrapping if need be. See `Cheap::slide_right`.
+     */
+    pub fn slide_right(&mut self) {
+        self.try_slide_right().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `slide_right`, but returns an error instead of panicking.
+    pub fn try_slide_right(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::SlideRight, None, |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            if ring.len == ring.a.len() {
+                return Err(CheapError::Full);
+            }
+            if ring.is_empty() {
+                ring.lo = ring.phys(1);
+            } else {
+                let len = ring.len;
+                ring.swap(ring.lo, ring.phys(len));
+                if ring.c == 0 {
+                    ring.lo = ring.phys(1);
+                    ring.c = len - 1;
+                    ring.recenter();
+                } else {
+                    ring.sift_in(len);
+                    ring.lo = ring.phys(1);
+                    ring.c -= 1;
+                }
+            }
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(())
+        })
+    }
+
+    /**
+     * Transfer the value before `lo` over to the right, moving the whole range one slot to the
+     * left and wrapping if need be. See `Cheap::slide_left`.
+     */
+    pub fn slide_left(&mut self) {
+        self.try_slide_left().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `slide_left`, but returns an error instead of panicking.
+    pub fn try_slide_left(&mut self) -> Result<(), CheapError> {
+        self.op(CheapOp::SlideLeft, None, |ring| {
+            #[cfg(debug_assertions)]
+            ring.check();
+            let n = ring.a.len();
+            if ring.len == n {
+                return Err(CheapError::Full);
+            }
+            ring.lo = (ring.lo + n - 1) % n;
+            if !ring.is_empty() {
+                // Offsets are now from the new lo, so the old range is 1..=len.
+                let len = ring.len;
+                let old_c = ring.c + 1;
+                ring.swap(ring.lo, ring.phys(len));
+                if old_c == len {
+                    ring.c = 0;
+                    ring.recenter();
+                } else {
+                    ring.c = old_c;
+                    ring.sift_in(0);
+                }
+            }
+            #[cfg(debug_assertions)]
+            ring.check();
+            Ok(())
+        })
+    }
+}
+
+impl<'a, E: fmt::Debug, C: Counter + fmt::Debug, O> fmt::Debug for RingCheap<'a, E, C, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lo, c, hi) = self.params();
+        let a_len = self.a.len();
+        f.write_str("(")?;
+        for i in 0..a_len {
+            if a_len >= 100 && i == 40 {
+                f.write_str(" ...")?;
+            }
+            if a_len >= 100 && (40..a_len - 40).contains(&i) {
+                continue;
+            }
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(format!("{:?}", &self.a[i]).as_str())?;
+            if self.contains(i) && i == c {
+                f.write_str(":c")?;
+            }
+            if i == lo && !self.is_empty() {
+                f.write_str(":lo")?;
+            }
+            if i == hi && self.len < a_len {
+                f.write_str(":hi")?;
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+/**
+ * A running sort over an unbounded stream, using `buf` as a ring.
+ *
+ * Like `running_sort_left`, each value is pushed into a c-heap and, once it holds `run` values,
+ * the best is popped and handed to `emit`. The heap slides around `buf`, which needs at least
+ * `run` slots; its initial contents are overwritten and its final contents are unspecified.
+ */
+pub fn running_sort_stream<E, I, F, C>(
+    buf: &mut [E],
+    run: usize,
+    input: I,
+    mut emit: F,
+    cnt: &mut C,
+) where
+    E: PartialOrd + fmt::Debug,
+    I: IntoIterator<Item = E>,
+    F: FnMut(&E),
+    C: Counter + fmt::Debug,
+{
+    let run = run.max(1);
+    assert!(
+        buf.len() >= run,
+        "running_sort_stream: ring smaller than run"
+    );
+    let mut ring = RingCheap::new(buf, cnt);
+    for v in input {
+        ring.insert_right(v);
+        if ring.len() >= run {
+            let i = ring.pop_left();
+            emit(&ring.as_slice()[i]);
+        }
+    }
+    while !ring.is_empty() {
+        let i = ring.pop_left();
+        emit(&ring.as_slice()[i]);
+    }
+}
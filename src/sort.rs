@@ -0,0 +1,837 @@
+use crate::cheap::Cheap;
+use crate::counter::Counter;
+use crate::order::{ByKey, ByOrdering, NaturalOrder, Order, Reversed};
+use std::cmp::Ordering;
+use std::fmt;
+
+/**
+ * Insertion sort `a[lo..hi]`; used for the leaves of `merge_sort`.
+ */
+pub fn small_sort<E: PartialOrd, C: Counter>(a: &mut [E], lo: usize, hi: usize, c: &mut C) {
+    small_sort_with(a, lo, hi, &mut NaturalOrder, c)
+}
+
+/**
+ * Insertion sort `a[lo..hi]` as ranked by `ord`.
+ */
+pub fn small_sort_with<E, O: Order<E>, C: Counter>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    ord: &mut O,
+    c: &mut C,
+) {
+    debug_assert!(
+        /*0 <= lo && */ lo <= hi && hi <= a.len(),
+        "small_sort(pre): length invariants"
+    );
+
+    for i in lo + 1..hi {
+        let mut j = i;
+        while j > lo && ord.lt(&a[j], &a[j - 1]) {
+            c.count_compare();
+            a.swap(j - 1, j);
+            c.count_swap_at(j - 1, j);
+            j -= 1;
+        }
+        c.count_compare();
+    }
+    debug_assert!(
+        is_sorted_with(a, lo, hi, ord),
+        "small_sort(post): not sorted"
+    );
+}
+
+/**
+ * Check that `a[lo..hi]` is in ascending order.
+ */
+pub fn is_sorted<E: PartialOrd>(a: &[E], lo: usize, hi: usize) -> bool {
+    is_sorted_with(a, lo, hi, &mut NaturalOrder)
+}
+
+/**
+ * Check that `a[lo..hi]` is in order as ranked by `ord`.
+ */
+pub fn is_sorted_with<E, O: Order<E>>(a: &[E], lo: usize, hi: usize, ord: &mut O) -> bool {
+    assert!(
+        /*0 <= lo && */ lo <= hi && hi <= a.len(),
+        "is_sorted(pre): length invariants"
+    );
+    if lo == hi {
+        return true;
+    }
+
+    let mut v = &a[lo];
+    for vv in &a[lo + 1..hi] {
+        if ord.lt(vv, v) {
+            return false;
+        }
+        v = vv;
+    }
+    true
+}
+
+/**
+ * Sort `a[lo..hi]` by recursively splitting it in half, sorting small chunks with `small_sort`
+ * and combining the halves with `merge`, typically `Cheap::merge`.
+ */
+pub fn merge_sort<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    merge: fn(&mut [E], lo: usize, md: usize, hi: usize, cnt: &mut C),
+    cnt: &mut C,
+) {
+    let mut merge =
+        |a: &mut [E], lo, md, hi, _: &mut NaturalOrder, cnt: &mut C| merge(a, lo, md, hi, cnt);
+    merge_sort_rec(a, lo, hi, &mut merge, &mut NaturalOrder, cnt);
+}
+
+/**
+ * Sort `a[lo..hi]` as ranked by `ord`, combining halves with `merge`, typically
+ * `Cheap::merge_with`.
+ */
+pub fn merge_sort_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    merge: fn(&mut [E], lo: usize, md: usize, hi: usize, ord: &mut O, cnt: &mut C),
+    ord: &mut O,
+    cnt: &mut C,
+) {
+    merge_sort_rec(a, lo, hi, &mut { merge }, ord, cnt);
+}
+
+/**
+ * Sort `a[lo..hi]` with `compare`, merging with `Cheap::merge_with`.
+ */
+pub fn merge_sort_by<E: fmt::Debug, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    compare: F,
+    cnt: &mut C,
+) {
+    merge_sort_with(a, lo, hi, Cheap::merge_with, &mut ByOrdering(compare), cnt);
+}
+
+/**
+ * Sort `a[lo..hi]` by the keys `key` extracts, merging with `Cheap::merge_with`.
+ */
+pub fn merge_sort_by_key<E: fmt::Debug, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    key: F,
+    cnt: &mut C,
+) {
+    merge_sort_with(a, lo, hi, Cheap::merge_with, &mut ByKey(key), cnt);
+}
+
+fn merge_sort_rec<E, O, C, M>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    merge: &mut M,
+    ord: &mut O,
+    cnt: &mut C,
+) where
+    O: Order<E>,
+    C: Counter,
+    M: FnMut(&mut [E], usize, usize, usize, &mut O, &mut C),
+{
+    debug_assert!(
+        /*0 <= lo && */ lo <= hi && hi <= a.len(),
+        "merge_sort(pre): length invariants"
+    );
+    if hi - lo <= 4 {
+        small_sort_with(a, lo, hi, ord, cnt);
+        return;
+    }
+
+    let midpoint = (lo + hi) / 2;
+    dbg_println!("merge_sort: lo={}, md={}, hi={}", lo, midpoint, hi);
+    merge_sort_rec(a, lo, midpoint, merge, ord, cnt);
+    merge_sort_rec(a, midpoint, hi, merge, ord, cnt);
+    merge(a, lo, midpoint, hi, ord, cnt);
+    debug_assert!(
+        is_sorted_with(a, lo, hi, ord),
+        "merge_sort(post): not sorted"
+    );
+}
+
+// Block size `bottom_up_merge_sort` uses when none is given; the same chunk size `merge_sort`
+// leaves to `small_sort`.
+const DEFAULT_BLOCK: usize = 4;
+
+/**
+ * Sort `a[lo..hi]` without recursion: sort blocks of `block` elements with `small_sort`, then
+ * combine neighboring blocks with `merge`, typically `Cheap::merge`, doubling the width each pass.
+ *
+ * ```text
+ *      [b][b][b][b][b][b][b]
+ *      [ 2b ][ 2b ][ 2b ][b]
+ *      [    4b    ][  3b  ]
+ *      [         7b       ]
+ * ```
+ *
+ * `block` defaults to 4 elements. `small_sort` is an insertion sort, so large blocks get slow.
+ */
+pub fn bottom_up_merge_sort<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    block: Option<usize>,
+    merge: fn(&mut [E], lo: usize, md: usize, hi: usize, cnt: &mut C),
+    cnt: &mut C,
+) {
+    let mut merge =
+        |a: &mut [E], lo, md, hi, _: &mut NaturalOrder, cnt: &mut C| merge(a, lo, md, hi, cnt);
+    bottom_up_merge_sort_impl(a, lo, hi, block, &mut merge, &mut NaturalOrder, cnt);
+}
+
+/**
+ * Sort `a[lo..hi]` bottom-up as ranked by `ord`, combining blocks with `merge`, typically
+ * `Cheap::merge_with`.
+ */
+pub fn bottom_up_merge_sort_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    block: Option<usize>,
+    merge: fn(&mut [E], lo: usize, md: usize, hi: usize, ord: &mut O, cnt: &mut C),
+    ord: &mut O,
+    cnt: &mut C,
+) {
+    bottom_up_merge_sort_impl(a, lo, hi, block, &mut { merge }, ord, cnt);
+}
+
+/**
+ * Sort `a[lo..hi]` bottom-up with `compare`, merging with `Cheap::merge_with`.
+ */
+pub fn bottom_up_merge_sort_by<
+    E: fmt::Debug,
+    F: FnMut(&E, &E) -> Ordering,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    block: Option<usize>,
+    compare: F,
+    cnt: &mut C,
+) {
+    bottom_up_merge_sort_with(
+        a,
+        lo,
+        hi,
+        block,
+        Cheap::merge_with,
+        &mut ByOrdering(compare),
+        cnt,
+    );
+}
+
+/**
+ * Sort `a[lo..hi]` bottom-up by the keys `key` extracts, merging with `Cheap::merge_with`.
+ */
+pub fn bottom_up_merge_sort_by_key<
+    E: fmt::Debug,
+    K: Ord,
+    F: FnMut(&E) -> K,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    block: Option<usize>,
+    key: F,
+    cnt: &mut C,
+) {
+    bottom_up_merge_sort_with(a, lo, hi, block, Cheap::merge_with, &mut ByKey(key), cnt);
+}
+
+fn bottom_up_merge_sort_impl<E, O, C, M>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    block: Option<usize>,
+    merge: &mut M,
+    ord: &mut O,
+    cnt: &mut C,
+) where
+    O: Order<E>,
+    C: Counter,
+    M: FnMut(&mut [E], usize, usize, usize, &mut O, &mut C),
+{
+    debug_assert!(
+        /*0 <= lo && */ lo <= hi && hi <= a.len(),
+        "bottom_up_merge_sort(pre): length invariants"
+    );
+    let block = block.unwrap_or(DEFAULT_BLOCK).max(1);
+    for start in (lo..hi).step_by(block) {
+        small_sort_with(a, start, (start + block).min(hi), ord, cnt);
+    }
+    let mut width = block;
+    while width < hi - lo {
+        for start in (lo..hi).step_by(2 * width) {
+            let md = start + width;
+            if md >= hi {
+                break;
+            }
+            let end = (md + width).min(hi);
+            dbg_println!("bottom_up_merge_sort: lo={}, md={}, hi={}", start, md, end);
+            merge(a, start, md, end, ord, cnt);
+        }
+        width = width.saturating_mul(2);
+    }
+    debug_assert!(
+        is_sorted_with(a, lo, hi, ord),
+        "bottom_up_merge_sort(post): not sorted"
+    );
+}
+
+// Runs shorter than this are padded out with `small_sort` before `natural_merge_sort` merges
+// them; it matches the chunk size `merge_sort` leaves to `small_sort`.
+const MIN_RUN: usize = 4;
+
+/**
+ * Sort `a[lo..hi]` by merging the runs already in it with `Cheap::merge`.
+ *
+ * Ascending runs are used as they are, and strictly descending runs are reversed. Runs shorter
+ * than `MIN_RUN` are padded out with `small_sort`. As in TimSort, runs wait on a stack until
+ * their lengths say they're due to be merged with a neighbor:
+ *
+ * ```text
+ *      [    A    ][   B   ][ C ]
+ * ```
+ *
+ * Runs are merged while `A <= B + C` or `B <= C`, pairing `B` with whichever of `A` and `C` is
+ * shorter, so the stack stays logarithmic in length and merges stay balanced. Input that's
+ * already sorted, or sorted backwards, takes a single pass.
+ */
+pub fn natural_merge_sort<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    cnt: &mut C,
+) {
+    natural_merge_sort_with(a, lo, hi, &mut NaturalOrder, cnt);
+}
+
+/**
+ * Sort `a[lo..hi]` as ranked by `ord`, merging the runs already in it with `Cheap::merge_with`.
+ */
+pub fn natural_merge_sort_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    ord: &mut O,
+    cnt: &mut C,
+) {
+    debug_assert!(
+        /*0 <= lo && */ lo <= hi && hi <= a.len(),
+        "natural_merge_sort(pre): length invariants"
+    );
+    // Each entry is the start of a run; a run ends where the next one starts.
+    let mut runs: Vec<usize> = Vec::new();
+    let mut i = lo;
+    while i < hi {
+        let mut end = find_run(a, i, hi, ord, cnt);
+        if end - i < MIN_RUN {
+            let padded = (i + MIN_RUN).min(hi);
+            small_sort_with(a, i, padded, ord, cnt);
+            end = extend_run(a, padded, hi, ord, cnt);
+        }
+        dbg_println!("natural_merge_sort: run lo={}, hi={}", i, end);
+        runs.push(i);
+        i = end;
+        collapse_runs(a, &mut runs, end, false, ord, cnt);
+    }
+    collapse_runs(a, &mut runs, hi, true, ord, cnt);
+    debug_assert!(
+        is_sorted_with(a, lo, hi, ord),
+        "natural_merge_sort(post): not sorted"
+    );
+}
+
+/**
+ * Sort `a[lo..hi]` with `compare`, merging the runs already in it.
+ */
+pub fn natural_merge_sort_by<
+    E: fmt::Debug,
+    F: FnMut(&E, &E) -> Ordering,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    compare: F,
+    cnt: &mut C,
+) {
+    natural_merge_sort_with(a, lo, hi, &mut ByOrdering(compare), cnt);
+}
+
+/**
+ * Sort `a[lo..hi]` by the keys `key` extracts, merging the runs already in it.
+ */
+pub fn natural_merge_sort_by_key<
+    E: fmt::Debug,
+    K: Ord,
+    F: FnMut(&E) -> K,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    key: F,
+    cnt: &mut C,
+) {
+    natural_merge_sort_with(a, lo, hi, &mut ByKey(key), cnt);
+}
+
+/*
+ * Find the end of the run starting at `lo`, reversing it first if it's strictly descending.
+ * Descending runs must be strict, or reversing them would reorder equal elements.
+ */
+fn find_run<E, O: Order<E>, C: Counter>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    ord: &mut O,
+    cnt: &mut C,
+) -> usize {
+    if lo + 1 >= hi {
+        return hi;
+    }
+    cnt.count_compare();
+    if !ord.lt(&a[lo + 1], &a[lo]) {
+        return extend_run(a, lo + 2, hi, ord, cnt);
+    }
+    let mut end = lo + 2;
+    while end < hi {
+        cnt.count_compare();
+        if !ord.lt(&a[end], &a[end - 1]) {
+            break;
+        }
+        end += 1;
+    }
+    let (mut i, mut j) = (lo, end - 1);
+    while i < j {
+        cnt.count_swap_at(i, j);
+        a.swap(i, j);
+        i += 1;
+        j -= 1;
+    }
+    end
+}
+
+/*
+ * Given `a[..end]` ends in an ascending run, find where that run really ends.
+ */
+fn extend_run<E, O: Order<E>, C: Counter>(
+    a: &[E],
+    mut end: usize,
+    hi: usize,
+    ord: &mut O,
+    cnt: &mut C,
+) -> usize {
+    while end < hi {
+        cnt.count_compare();
+        if ord.lt(&a[end], &a[end - 1]) {
+            break;
+        }
+        end += 1;
+    }
+    end
+}
+
+/*
+ * Merge runs off the top of the stack until the stack rule holds again or, with `all`, until
+ * there's one run left. `hi` is where the top run ends.
+ */
+fn collapse_runs<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    runs: &mut Vec<usize>,
+    hi: usize,
+    all: bool,
+    ord: &mut O,
+    cnt: &mut C,
+) {
+    let run_len = |runs: &[usize], n: usize| runs.get(n + 1).copied().unwrap_or(hi) - runs[n];
+    while runs.len() > 1 {
+        let mut n = runs.len() - 2;
+        let shorter_left = n > 0 && run_len(runs, n - 1) < run_len(runs, n + 1);
+        if all
+            || (n > 0 && run_len(runs, n - 1) <= run_len(runs, n) + run_len(runs, n + 1))
+            || (n > 1 && run_len(runs, n - 2) <= run_len(runs, n - 1) + run_len(runs, n))
+        {
+            if shorter_left {
+                n -= 1;
+            }
+        } else if run_len(runs, n) > run_len(runs, n + 1) {
+            break;
+        }
+        let (lo, md, end) = (runs[n], runs[n + 1], runs.get(n + 2).copied().unwrap_or(hi));
+        dbg_println!("natural_merge_sort: lo={}, md={}, hi={}", lo, md, end);
+        Cheap::merge_with(a, lo, md, end, ord, cnt);
+        runs.remove(n + 1);
+    }
+}
+
+/**
+ * Heap sort ascending by building a c-heap centered at the right and popping to the left.
+ */
+pub fn heap_sort_left<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    cnt: &mut C,
+) {
+    heap_sort_left_with(a, NaturalOrder, cnt)
+}
+
+/// Heap sort to the left as ranked by `ord`.
+pub fn heap_sort_left_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    ord: O,
+    cnt: &mut C,
+) {
+    let mut c = Cheap::new_spanright(a, cnt).with_order(ord);
+    c.recenter();
+    while !c.is_empty() {
+        c.pop_left();
+    }
+}
+
+/// Heap sort to the left with `compare`.
+pub fn heap_sort_left_by<E: fmt::Debug, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    compare: F,
+    cnt: &mut C,
+) {
+    heap_sort_left_with(a, ByOrdering(compare), cnt)
+}
+
+/// Heap sort to the left by the keys `key` extracts.
+pub fn heap_sort_left_by_key<E: fmt::Debug, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    key: F,
+    cnt: &mut C,
+) {
+    heap_sort_left_with(a, ByKey(key), cnt)
+}
+
+/**
+ * Heap sort ascending by building a c-heap centered at the left, popping to the right and
+ * then reversing the array.
+ */
+pub fn heap_sort_right<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    cnt: &mut C,
+) {
+    heap_sort_right_with(a, NaturalOrder, cnt)
+}
+
+/// Heap sort to the right as ranked by `ord`.
+pub fn heap_sort_right_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    ord: O,
+    cnt: &mut C,
+) {
+    let mut c = Cheap::new_spanleft(a, cnt).with_order(ord);
+    c.recenter();
+    while !c.is_empty() {
+        c.pop_right();
+    }
+    cnt.count_reverse(0, a.len());
+    a.reverse();
+}
+
+/// Heap sort to the right with `compare`.
+pub fn heap_sort_right_by<E: fmt::Debug, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    compare: F,
+    cnt: &mut C,
+) {
+    heap_sort_right_with(a, ByOrdering(compare), cnt)
+}
+
+/// Heap sort to the right by the keys `key` extracts.
+pub fn heap_sort_right_by_key<E: fmt::Debug, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    key: F,
+    cnt: &mut C,
+) {
+    heap_sort_right_with(a, ByKey(key), cnt)
+}
+
+/**
+ * Move the `k` best elements of `a` to `a[..k]` in order, leaving the rest in unspecified
+ * order. Recenters a c-heap over the whole array, then pops `k` times to the left.
+ */
+pub fn partial_sort<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    k: usize,
+    cnt: &mut C,
+) {
+    partial_sort_with(a, k, NaturalOrder, cnt)
+}
+
+/// Partial sort as ranked by `ord`.
+pub fn partial_sort_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    k: usize,
+    ord: O,
+    cnt: &mut C,
+) {
+    let mut c = Cheap::new_spanright(a, cnt).with_order(ord);
+    c.recenter();
+    for _ in 0..k.min(c.len()) {
+        c.pop_left();
+    }
+}
+
+/// Partial sort with `compare`.
+pub fn partial_sort_by<E: fmt::Debug, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    k: usize,
+    compare: F,
+    cnt: &mut C,
+) {
+    partial_sort_with(a, k, ByOrdering(compare), cnt)
+}
+
+/// Partial sort by the keys `key` extracts.
+pub fn partial_sort_by_key<E: fmt::Debug, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    k: usize,
+    key: F,
+    cnt: &mut C,
+) {
+    partial_sort_with(a, k, ByKey(key), cnt)
+}
+
+/**
+ * Select the `k` smallest elements of `a` with `partial_sort`, and return them. They come out
+ * in order.
+ */
+pub fn select_top_k<'a, E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &'a mut [E],
+    k: usize,
+    cnt: &mut C,
+) -> &'a mut [E] {
+    let k = k.min(a.len());
+    partial_sort(a, k, cnt);
+    &mut a[..k]
+}
+
+/**
+ * Check that `a[..k]` is sorted and everything after it is at least `a[k - 1]`.
+ */
+pub fn is_partially_sorted<E: PartialOrd>(a: &[E], k: usize) -> bool {
+    let k = k.min(a.len());
+    if !is_sorted(a, 0, k) {
+        return false;
+    }
+    k == 0 || a[k..].iter().all(|v| a[k - 1] <= *v)
+}
+
+/**
+ * Put the `n`th smallest element at `a[n]`, with nothing greater before it and nothing less
+ * after it, like `slice::select_nth_unstable`. Returns the selected element.
+ *
+ * A c-heap over the whole array pops the best elements in order from whichever end is nearer
+ * `n`: to the left with the smallest first, or to the right with the largest first.
+ *
+ * Panics if `n >= a.len()`.
+ */
+pub fn select_nth<'a, E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &'a mut [E],
+    n: usize,
+    cnt: &mut C,
+) -> &'a mut E {
+    select_nth_with(a, n, NaturalOrder, cnt)
+}
+
+/// Select the `n`th element as ranked by `ord`.
+pub fn select_nth_with<'a, E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &'a mut [E],
+    n: usize,
+    ord: O,
+    cnt: &mut C,
+) -> &'a mut E {
+    let a_len = a.len();
+    assert!(n < a_len, "select_nth: index {} out of range", n);
+    if n < a_len / 2 {
+        let mut c = Cheap::new_spanright(a, cnt).with_order(ord);
+        c.recenter();
+        for _ in 0..=n {
+            c.pop_left();
+        }
+    } else {
+        let mut c = Cheap::new_spanleft(a, cnt).with_order(Reversed(ord));
+        c.recenter();
+        for _ in n..a_len {
+            c.pop_right();
+        }
+    }
+    &mut a[n]
+}
+
+/// Select the `n`th element as ordered by `compare`.
+pub fn select_nth_by<'a, E: fmt::Debug, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &'a mut [E],
+    n: usize,
+    compare: F,
+    cnt: &mut C,
+) -> &'a mut E {
+    select_nth_with(a, n, ByOrdering(compare), cnt)
+}
+
+/// Select the `n`th element by the keys `key` extracts.
+pub fn select_nth_by_key<'a, E: fmt::Debug, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &'a mut [E],
+    n: usize,
+    key: F,
+    cnt: &mut C,
+) -> &'a mut E {
+    select_nth_with(a, n, ByKey(key), cnt)
+}
+
+/**
+ * Check that nothing before `a[n]` is greater than it and nothing after it is less.
+ */
+pub fn is_selected<E: PartialOrd>(a: &[E], n: usize) -> bool {
+    n < a.len() && a[..n].iter().all(|v| *v <= a[n]) && a[n + 1..].iter().all(|v| a[n] <= *v)
+}
+
+/**
+ * This running sort starts at the left, pushes from the right until it's `run` elements large,
+ * then pops elements.
+ */
+pub fn running_sort_left<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    run: usize,
+    cnt: &mut C,
+) {
+    running_sort_left_with(a, run, NaturalOrder, cnt)
+}
+
+/// Running sort to the left as ranked by `ord`.
+pub fn running_sort_left_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    run: usize,
+    ord: O,
+    cnt: &mut C,
+) {
+    let a_len = a.len();
+    if a_len == 0 {
+        return;
+    }
+    let mut c = Cheap::new_left(a, cnt).with_order(ord);
+    while c.lo() < a_len {
+        if c.hi() < a_len {
+            c.push_right();
+        }
+        if c.len() >= run || c.hi() == a_len {
+            c.pop_left();
+        }
+        if a_len < 200 {
+            dbg_show_call!(c, "running_left(");
+        }
+    }
+}
+
+/// Running sort to the left with `compare`.
+pub fn running_sort_left_by<
+    E: fmt::Debug,
+    F: FnMut(&E, &E) -> Ordering,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    run: usize,
+    compare: F,
+    cnt: &mut C,
+) {
+    running_sort_left_with(a, run, ByOrdering(compare), cnt)
+}
+
+/// Running sort to the left by the keys `key` extracts.
+pub fn running_sort_left_by_key<
+    E: fmt::Debug,
+    K: Ord,
+    F: FnMut(&E) -> K,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    run: usize,
+    key: F,
+    cnt: &mut C,
+) {
+    running_sort_left_with(a, run, ByKey(key), cnt)
+}
+
+/**
+ * This running sort starts at the right, pushes from the left until it's `run` elements large,
+ * then pops elements.
+ */
+pub fn running_sort_right<E: PartialOrd + fmt::Debug, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    run: usize,
+    cnt: &mut C,
+) {
+    running_sort_right_with(a, run, NaturalOrder, cnt)
+}
+
+/// Running sort to the right as ranked by `ord`.
+pub fn running_sort_right_with<E: fmt::Debug, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    run: usize,
+    ord: O,
+    cnt: &mut C,
+) {
+    let a_len = a.len();
+    if a_len == 0 {
+        return;
+    }
+    let mut c = Cheap::new_right(a, cnt).with_order(ord);
+    while c.hi() > 0 {
+        if c.lo() > 0 {
+            c.push_left();
+        }
+        if c.len() >= run || c.lo() == 0 {
+            c.pop_right();
+        }
+        if a_len < 200 {
+            dbg_show_call!(c, "running_right(");
+        }
+    }
+}
+
+/// Running sort to the right with `compare`.
+pub fn running_sort_right_by<
+    E: fmt::Debug,
+    F: FnMut(&E, &E) -> Ordering,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    run: usize,
+    compare: F,
+    cnt: &mut C,
+) {
+    running_sort_right_with(a, run, ByOrdering(compare), cnt)
+}
+
+/// Running sort to the right by the keys `key` extracts.
+pub fn running_sort_right_by_key<
+    E: fmt::Debug,
+    K: Ord,
+    F: FnMut(&E) -> K,
+    C: Counter + fmt::Debug,
+>(
+    a: &mut [E],
+    run: usize,
+    key: F,
+    cnt: &mut C,
+) {
+    running_sort_right_with(a, run, ByKey(key), cnt)
+}
@@ -0,0 +1,161 @@
+use crate::cheap::Cheap;
+use crate::counter::Counter;
+use crate::order::{ByKey, ByOrdering, NaturalOrder, Order};
+use crate::sort::merge_sort_with;
+use std::cmp::Ordering;
+use std::fmt;
+
+/*
+ * Ranks positions in `a` by the elements found there, breaking ties on the position itself.
+ *
+ * Since no two positions are equal, no two elements tie, and whatever order the c-heap pops
+ * them in is the stable one.
+ */
+pub(crate) struct StableOrder<'s, E, O> {
+    pub(crate) a: &'s [E],
+    pub(crate) ord: O,
+}
+
+impl<'s, E, O: Order<E>> Order<usize> for StableOrder<'s, E, O> {
+    fn le(&mut self, i: &usize, j: &usize) -> bool {
+        self.lt(i, j) || i == j
+    }
+    fn lt(&mut self, i: &usize, j: &usize) -> bool {
+        let (x, y) = (&self.a[*i], &self.a[*j]);
+        self.ord.lt(x, y) || (i < j && !self.ord.lt(y, x))
+    }
+}
+
+/*
+ * Move each element of `a[lo..]` to where `idx` says it belongs: afterwards, `a[lo + k]` holds
+ * what was at `a[lo + idx[k]]`. Follows each cycle of the permutation, so it takes at most one
+ * swap per element.
+ *
+ * Side-effect: `idx` becomes the identity.
+ */
+pub(crate) fn apply_permutation<E, C: Counter>(
+    a: &mut [E],
+    lo: usize,
+    idx: &mut [usize],
+    cnt: &mut C,
+) {
+    for start in 0..idx.len() {
+        let mut j = start;
+        loop {
+            let k = idx[j];
+            idx[j] = j;
+            if k == start {
+                break;
+            }
+            cnt.count_swap_at(lo + j, lo + k);
+            a.swap(lo + j, lo + k);
+            j = k;
+        }
+    }
+}
+
+/*
+ * Sort the positions `lo..hi`, either fully or, when `md` is given, by merging the sorted runs
+ * `lo..md` and `md..hi`, then permute `a` to match.
+ */
+fn stable_by_position<E, O: Order<E>, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: Option<usize>,
+    hi: usize,
+    ord: O,
+    cnt: &mut C,
+) {
+    debug_assert!(
+        /*0 <= lo && */ lo <= hi && hi <= a.len(),
+        "stable(pre): length invariants"
+    );
+    let mut idx: Vec<usize> = (0..hi - lo).collect();
+    let n = idx.len();
+    {
+        let mut so = StableOrder { a: &a[lo..hi], ord };
+        match md {
+            Some(md) => Cheap::merge_with(&mut idx, 0, md - lo, n, &mut so, cnt),
+            None => merge_sort_with(&mut idx, 0, n, Cheap::merge_with, &mut so, cnt),
+        }
+    }
+    apply_permutation(a, lo, &mut idx, cnt);
+}
+
+/**
+ * Stably merge the sorted runs `lo..md` and `md..hi`: equal elements keep their relative order,
+ * with those from `lo..md` first.
+ *
+ * Unlike `Cheap::merge`, this is not in place. The c-heap merges positions rather than
+ * elements, with ties broken on position, and the elements are then moved into place. The
+ * positions live in a scratch `Vec<usize>` of `hi - lo` words, so it takes O(n) extra space,
+ * allocated on every call.
+ */
+pub fn stable_merge<E: PartialOrd, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    cnt: &mut C,
+) {
+    stable_by_position(a, lo, Some(md), hi, NaturalOrder, cnt)
+}
+
+/// Stably merge the runs `lo..md` and `md..hi` as ordered by `compare`.
+pub fn stable_merge_by<E, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    compare: F,
+    cnt: &mut C,
+) {
+    stable_by_position(a, lo, Some(md), hi, ByOrdering(compare), cnt)
+}
+
+/// Stably merge the runs `lo..md` and `md..hi` as ordered by the keys `key` extracts.
+pub fn stable_merge_by_key<E, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    md: usize,
+    hi: usize,
+    key: F,
+    cnt: &mut C,
+) {
+    stable_by_position(a, lo, Some(md), hi, ByKey(key), cnt)
+}
+
+/**
+ * Stably sort `a[lo..hi]` with `merge_sort` on the c-heap: equal elements keep their original
+ * order. Like `stable_merge`, it is not in place: it allocates `hi - lo` words of scratch space.
+ */
+pub fn stable_merge_sort<E: PartialOrd, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    cnt: &mut C,
+) {
+    stable_by_position(a, lo, None, hi, NaturalOrder, cnt)
+}
+
+/// Stably sort `a[lo..hi]` with `compare`.
+pub fn stable_merge_sort_by<E, F: FnMut(&E, &E) -> Ordering, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    compare: F,
+    cnt: &mut C,
+) {
+    stable_by_position(a, lo, None, hi, ByOrdering(compare), cnt)
+}
+
+/// Stably sort `a[lo..hi]` by the keys `key` extracts.
+pub fn stable_merge_sort_by_key<E, K: Ord, F: FnMut(&E) -> K, C: Counter + fmt::Debug>(
+    a: &mut [E],
+    lo: usize,
+    hi: usize,
+    key: F,
+    cnt: &mut C,
+) {
+    stable_by_position(a, lo, None, hi, ByKey(key), cnt)
+}
@@ -0,0 +1,309 @@
+use crate::cheap::Cheap;
+use crate::counter::{CheapOp, Counter, DummyCounter, Sift};
+use crate::error::ReplayError;
+use crate::sort::is_sorted;
+use json::JsonValue;
+use std::fmt;
+use std::io::{self, Write};
+
+/**
+ * A counter that writes a trace of every c-heap operation and every swap, one JSON object per
+ * line, and passes everything on to another counter.
+ *
+ * Each operation is written as it starts, with its markers and any index it was given, and
+ * again as it finishes, with the markers it left. Everything it did comes in between:
+ *
+ * ```text
+ *     {"call":"poppush","lo":4,"c":6,"hi":9,"arg":1}
+ *     {"swap":[1,6]}
+ *     {"swap":[6,7]}
+ *     {"done":"poppush","lo":4,"c":6,"hi":9}
+ * ```
+ *
+ * `Merge` records the bounds of the merge in place of markers. Swaps outside any operation,
+ * such as the ones a merge sort makes on short runs, are written too, as are reversals like
+ * the one that ends `heap_sort_right`, so the trace accounts for every change to the array.
+ * `replay` reads it back.
+ *
+ * The counter methods can't fail, so the first write error is kept and returned by `finish`.
+ */
+pub struct Tracer<W: Write, C = DummyCounter> {
+    out: W,
+    cnt: C,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Tracer<W> {
+    /// Trace to `out` without counting anything else.
+    pub fn new(out: W) -> Self {
+        Tracer::with_counter(out, DummyCounter {})
+    }
+}
+
+impl<W: Write, C: Counter> Tracer<W, C> {
+    /// Trace to `out`, and pass everything on to `cnt`.
+    pub fn with_counter(out: W, cnt: C) -> Self {
+        Tracer {
+            out,
+            cnt,
+            error: None,
+        }
+    }
+
+    /// Flush the trace, and hand back the writer and the counter.
+    pub fn finish(mut self) -> io::Result<(W, C)> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.flush()?;
+        Ok((self.out, self.cnt))
+    }
+
+    fn emit(&mut self, event: fmt::Arguments<'_>) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.out, "{}", event) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl<W: Write, C: Counter> Counter for Tracer<W, C> {
+    fn count_compare(&mut self) {
+        self.cnt.count_compare();
+    }
+    fn count_swap(&mut self) {
+        self.cnt.count_swap();
+    }
+    fn count_move(&mut self) {
+        self.cnt.count_move();
+    }
+    fn count_swap_at(&mut self, i: usize, j: usize) {
+        self.emit(format_args!("{{\"swap\":[{},{}]}}", i, j));
+        self.cnt.count_swap_at(i, j);
+    }
+    fn copy_to(&self, tgt: &mut JsonValue) {
+        self.cnt.copy_to(tgt);
+    }
+    fn enter(&mut self, op: CheapOp, params: (usize, usize, usize), arg: Option<usize>) {
+        let (lo, c, hi) = params;
+        match arg {
+            Some(i) => self.emit(format_args!(
+                "{{\"call\":\"{}\",\"lo\":{},\"c\":{},\"hi\":{},\"arg\":{}}}",
+                op.name(),
+                lo,
+                c,
+                hi,
+                i
+            )),
+            None => self.emit(format_args!(
+                "{{\"call\":\"{}\",\"lo\":{},\"c\":{},\"hi\":{}}}",
+                op.name(),
+                lo,
+                c,
+                hi
+            )),
+        }
+        self.cnt.enter(op, params, arg);
+    }
+    fn leave(&mut self, op: CheapOp, params: (usize, usize, usize)) {
+        let (lo, c, hi) = params;
+        self.emit(format_args!(
+            "{{\"done\":\"{}\",\"lo\":{},\"c\":{},\"hi\":{}}}",
+            op.name(),
+            lo,
+            c,
+            hi
+        ));
+        self.cnt.leave(op, params);
+    }
+    fn count_recenter(&mut self, sifted: usize) {
+        self.cnt.count_recenter(sifted);
+    }
+    fn count_sift(&mut self, sift: Sift, levels: usize) {
+        self.cnt.count_sift(sift, levels);
+    }
+    fn count_reverse(&mut self, lo: usize, hi: usize) {
+        self.emit(format_args!("{{\"reverse\":[{},{}]}}", lo, hi));
+        self.cnt.count_reverse(lo, hi);
+    }
+}
+
+impl<W: Write, C: fmt::Debug> fmt::Debug for Tracer<W, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracer")
+            .field("cnt", &self.cnt)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+/// What `replay` got through.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Operations run again, not counting the ones they called.
+    pub calls: u64,
+    /// Swaps made outside any operation.
+    pub swaps: u64,
+    /// Ranges reversed outside any operation.
+    pub reversals: u64,
+    /// Lines checked against what replaying produced.
+    pub lines: u64,
+}
+
+/**
+ * Apply a trace written by `Tracer` to `a`, which should hold what the traced array held when
+ * tracing started.
+ *
+ * Swaps and reversals outside any operation are made as recorded. Each outermost operation is run again on a
+ * c-heap with the recorded markers, and everything it does must match the trace line for line.
+ * So afterwards `a` holds what the traced array held when tracing stopped, or the trace and
+ * the code disagree and the error says where.
+ *
+ * The c-heaps are assumed to have ranked elements by `PartialOrd` and to have worked on the
+ * array itself. Lines that aren't events, such as a header a caller wrote, are skipped.
+ */
+pub fn replay<E, S, I>(a: &mut [E], trace: I) -> Result<ReplayStats, ReplayError>
+where
+    E: PartialOrd + fmt::Debug,
+    S: AsRef<str>,
+    I: IntoIterator<Item = S>,
+{
+    let mut stats = ReplayStats::default();
+    let mut lines = trace.into_iter().enumerate().map(|(i, s)| (i + 1, s));
+    while let Some((line, text)) = lines.next() {
+        let text = text.as_ref().trim();
+        let malformed = || ReplayError::Malformed {
+            line,
+            text: text.to_string(),
+        };
+        let event = json::parse(text).map_err(|_| malformed())?;
+        if event.has_key("swap") {
+            match (event["swap"][0].as_usize(), event["swap"][1].as_usize()) {
+                (Some(i), Some(j)) if i < a.len() && j < a.len() => a.swap(i, j),
+                _ => return Err(malformed()),
+            }
+            stats.swaps += 1;
+        } else if event.has_key("reverse") {
+            match (
+                event["reverse"][0].as_usize(),
+                event["reverse"][1].as_usize(),
+            ) {
+                (Some(lo), Some(hi)) if lo <= hi && hi <= a.len() => a[lo..hi].reverse(),
+                _ => return Err(malformed()),
+            }
+            stats.reversals += 1;
+        } else if event.has_key("call") {
+            let rerun = rerun(a, line, text, &event)?;
+            let mut got = rerun.lines();
+            if got.next() != Some(text) {
+                return Err(malformed());
+            }
+            for g in got {
+                match lines.next() {
+                    Some((line, t)) if t.as_ref().trim() != g => {
+                        return Err(ReplayError::Mismatch {
+                            line,
+                            expected: t.as_ref().trim().to_string(),
+                            got: g.to_string(),
+                        })
+                    }
+                    Some(_) => stats.lines += 1,
+                    None => return Err(ReplayError::Truncated { got: g.to_string() }),
+                }
+            }
+            stats.calls += 1;
+        } else {
+            continue;
+        }
+        stats.lines += 1;
+    }
+    Ok(stats)
+}
+
+/*
+ * Run the operation a "call" event on line `line` describes on `a`, and return its trace. The
+ * event is malformed if its markers can't describe a c-heap in `a`, and a mismatch if `a`
+ * doesn't hold what the operation needs to start: a c-heap, or two sorted runs to merge. Debug
+ * builds are the only ones that check for the c-heap.
+ */
+fn rerun<E: PartialOrd + fmt::Debug>(
+    a: &mut [E],
+    line: usize,
+    text: &str,
+    event: &JsonValue,
+) -> Result<String, ReplayError> {
+    let malformed = || ReplayError::Malformed {
+        line,
+        text: text.to_string(),
+    };
+    let op = event["call"]
+        .as_str()
+        .and_then(CheapOp::from_name)
+        .ok_or_else(malformed)?;
+    let (lo, c, hi) = match (
+        event["lo"].as_usize(),
+        event["c"].as_usize(),
+        event["hi"].as_usize(),
+    ) {
+        (Some(lo), Some(c), Some(hi)) => (lo, c, hi),
+        _ => return Err(malformed()),
+    };
+    if lo > c || c > hi || hi > a.len() {
+        return Err(malformed());
+    }
+    // A merge's middle bound may sit at either end, but a c-heap's center is inside it.
+    let merge = op == CheapOp::Merge;
+    if !merge && (lo < hi && c == hi || lo == hi && c != lo) {
+        return Err(malformed());
+    }
+    let mismatch = |got: String| ReplayError::Mismatch {
+        line,
+        expected: text.to_string(),
+        got,
+    };
+    if merge && !(is_sorted(a, lo, c) && is_sorted(a, c, hi)) {
+        return Err(mismatch(format!("unsorted runs {}..{}..{}", lo, c, hi)));
+    }
+    let arg = || event["arg"].as_usize().ok_or_else(malformed);
+    let mut tr = Tracer::new(Vec::new());
+    if merge {
+        Cheap::merge(a, lo, c, hi, &mut tr);
+    } else {
+        let mut ch = Cheap::new_range(a, lo, c, hi, &mut tr);
+        /*
+         * Recentering builds the heap; everything else needs one to start with. Checking costs
+         * a pass over the heap, so only debug builds do it, where the operation would check
+         * and panic anyway. Release builds go ahead, and the swaps come out different.
+         */
+        if cfg!(debug_assertions) && op != CheapOp::Recenter && !ch.is_valid() {
+            return Err(mismatch(format!(
+                "no c-heap at lo={}, c={}, hi={}",
+                lo, c, hi
+            )));
+        }
+        // An operation that was refused is refused again, and the trace shows it either way.
+        let _ = match op {
+            CheapOp::Recenter => {
+                ch.recenter();
+                Ok(())
+            }
+            CheapOp::PopLeft => ch.try_pop_left(),
+            CheapOp::PopRight => ch.try_pop_right(),
+            CheapOp::PushLeft => ch.try_push_left(),
+            CheapOp::PushRight => ch.try_push_right(),
+            CheapOp::PushLeftSwap => ch.try_push_left_swap(arg()?),
+            CheapOp::PushRightSwap => ch.try_push_right_swap(arg()?),
+            CheapOp::Poppush => ch.try_poppush(arg()?),
+            CheapOp::Pushpop => ch.try_pushpop(arg()?),
+            CheapOp::SlideLeft => ch.try_slide_left(),
+            CheapOp::SlideRight => ch.try_slide_right(),
+            CheapOp::RemoveLeft => ch.try_remove_left(arg()?),
+            CheapOp::RemoveRight => ch.try_remove_right(arg()?),
+            CheapOp::Merge => unreachable!(),
+        };
+    }
+    // Writing to a Vec can't fail, and the trace is all ASCII.
+    let (out, _) = tr.finish().map_err(|_| malformed())?;
+    String::from_utf8(out).map_err(|_| malformed())
+}
@@ -0,0 +1,125 @@
+use crate::cheap::Cheap;
+use crate::counter::Counter;
+use crate::order::{NaturalOrder, Order, Reversed};
+use crate::stable::StableOrder;
+use std::fmt;
+
+/**
+ * Yields the best element of each `width`-wide window of a slice, from left to right.
+ *
+ * The c-heap holds positions into the data rather than the data itself, so the oldest element
+ * can be recognised when it surfaces at the center. Positions that fall out of the window stay
+ * in the heap until they do, so the heap can be wider than the window:
+ *
+ * ```text
+ *      popped      heap      to come
+ *      . . . . [x, x, C, x] n . . .
+ * ```
+ *
+ * Each step brings the next position `n` in through the high end. If the best position is
+ * stale, `poppush` swaps it out for `n` and `slide_right` passes it over to the low end, so the
+ * heap keeps its size. Otherwise `n` is pushed and the heap grows. Any stale positions left at
+ * the center are then popped to the left.
+ */
+#[derive(Debug)]
+pub struct SlidingWindow<'a, E, C: Counter + fmt::Debug, O = NaturalOrder> {
+    data: &'a [E],
+    width: usize,
+    pos: Vec<usize>,
+    lo: usize,
+    c: usize,
+    hi: usize,
+    next: usize,
+    ord: O,
+    cnt: &'a mut C,
+}
+
+impl<'a, E: PartialOrd, C: Counter + fmt::Debug> SlidingWindow<'a, E, C> {
+    /// Yield the minimum of each window.
+    pub fn min(data: &'a [E], width: usize, cnt: &'a mut C) -> Self {
+        SlidingWindow::with_order(data, width, NaturalOrder, cnt)
+    }
+}
+
+impl<'a, E: PartialOrd, C: Counter + fmt::Debug> SlidingWindow<'a, E, C, Reversed<NaturalOrder>> {
+    /// Yield the maximum of each window.
+    pub fn max(data: &'a [E], width: usize, cnt: &'a mut C) -> Self {
+        SlidingWindow::with_order(data, width, Reversed(NaturalOrder), cnt)
+    }
+}
+
+impl<'a, E, C: Counter + fmt::Debug, O: Order<E>> SlidingWindow<'a, E, C, O> {
+    /// Yield the best element of each window as ranked by `ord`. Ties go to the oldest.
+    ///
+    /// Panics if `width` is zero.
+    pub fn with_order(data: &'a [E], width: usize, ord: O, cnt: &'a mut C) -> Self {
+        assert!(width > 0, "SlidingWindow: width must be positive");
+        SlidingWindow {
+            data,
+            width,
+            pos: (0..data.len()).collect(),
+            lo: 0,
+            c: 0,
+            hi: 0,
+            next: 0,
+            ord,
+            cnt,
+        }
+    }
+
+    /// Width of each window.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of windows still to come.
+    pub fn steps_left(&self) -> usize {
+        (self.data.len() + 1).saturating_sub(self.next + self.width)
+    }
+
+    // Bring position `hi` into the heap, then drop stale positions older than `start`.
+    fn step(&mut self, start: usize) {
+        let mut ch = Cheap::new_range(&mut self.pos, self.lo, self.c, self.hi, self.cnt)
+            .with_order(StableOrder {
+                a: self.data,
+                ord: &mut self.ord,
+            });
+        let (_, _, hi) = ch.params();
+        if matches!(ch.peek(), Some(&p) if p < start) {
+            ch.poppush(hi);
+            ch.slide_right();
+        } else {
+            ch.push_right();
+        }
+        while matches!(ch.peek(), Some(&p) if p < start) {
+            ch.pop_left();
+        }
+        let (lo, c, hi) = ch.params();
+        self.lo = lo;
+        self.c = c;
+        self.hi = hi;
+    }
+}
+
+impl<'a, E, C: Counter + fmt::Debug, O: Order<E>> Iterator for SlidingWindow<'a, E, C, O> {
+    type Item = &'a E;
+
+    fn next(&mut self) -> Option<&'a E> {
+        if self.steps_left() == 0 {
+            return None;
+        }
+        let start = self.next;
+        while self.hi < start + self.width {
+            self.step(start);
+        }
+        self.next += 1;
+        Some(&self.data[self.pos[self.c]])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.steps_left();
+        (n, Some(n))
+    }
+}
+
+impl<'a, E, C: Counter + fmt::Debug, O: Order<E>> ExactSizeIterator for SlidingWindow<'a, E, C, O> {}
@@ -0,0 +1,91 @@
+use cheap::{bottom_up_merge_sort, bottom_up_merge_sort_by, Cheap, MergeStrategy, RealCounter};
+use std::cell::RefCell;
+
+thread_local! {
+    static MERGES: RefCell<Vec<(usize, usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+// Merge with the c-heap, noting down the ranges merged.
+fn noting_merge(a: &mut [i32], lo: usize, md: usize, hi: usize, cnt: &mut RealCounter) {
+    MERGES.with(|m| m.borrow_mut().push((lo, md, hi)));
+    Cheap::merge(a, lo, md, hi, cnt);
+}
+
+fn merges_made(n: usize, lo: usize, hi: usize, block: Option<usize>) -> Vec<(usize, usize, usize)> {
+    MERGES.with(|m| m.borrow_mut().clear());
+    let mut a: Vec<i32> = (0..n as i32).rev().collect();
+    bottom_up_merge_sort(
+        &mut a,
+        lo,
+        hi,
+        block,
+        noting_merge,
+        &mut RealCounter::default(),
+    );
+    assert!(a[lo..hi].windows(2).all(|w| w[0] <= w[1]));
+    MERGES.with(|m| m.borrow().clone())
+}
+
+#[test]
+fn widths_double_and_the_odd_block_out_waits() {
+    assert_eq!(
+        merges_made(7, 0, 7, Some(1)),
+        vec![
+            (0, 1, 2),
+            (2, 3, 4),
+            (4, 5, 6),
+            (0, 2, 4),
+            (4, 6, 7),
+            (0, 4, 7)
+        ]
+    );
+    // Ten elements in blocks of four: the last two only join in at the end.
+    assert_eq!(merges_made(10, 0, 10, None), vec![(0, 4, 8), (0, 8, 10)]);
+    // Offsets are kept when sorting a subrange.
+    assert_eq!(merges_made(12, 3, 9, Some(2)), vec![(3, 5, 7), (3, 7, 9)]);
+}
+
+#[test]
+fn block_zero_means_one_and_no_input_means_no_work() {
+    assert_eq!(merges_made(3, 0, 3, Some(0)), merges_made(3, 0, 3, Some(1)));
+    assert!(merges_made(0, 0, 0, None).is_empty());
+    assert!(merges_made(5, 2, 2, Some(1)).is_empty());
+    assert!(merges_made(5, 0, 5, Some(1000)).is_empty());
+}
+
+#[test]
+fn one_block_is_just_an_insertion_sort() {
+    // With every element in one block there's nothing to merge, so the merge is never called.
+    fn no_merge(_: &mut [i32], lo: usize, md: usize, hi: usize, _: &mut RealCounter) {
+        panic!("merged {}..{}..{}", lo, md, hi);
+    }
+    let mut a = vec![5, -1, 4, 4, 0, 9, 2];
+    let mut cnt = RealCounter::default();
+    bottom_up_merge_sort(&mut a, 0, 7, Some(7), no_merge, &mut cnt);
+    assert_eq!(a, vec![-1, 0, 2, 4, 4, 5, 9]);
+}
+
+#[test]
+fn sorts_a_subrange_by_comparator() {
+    let mut a: Vec<i32> = (0..40).map(|i| (i * 13) % 40).collect();
+    let mut expect = a.clone();
+    expect[5..35].sort_by(|x, y| y.cmp(x));
+    let mut cnt = RealCounter::default();
+    bottom_up_merge_sort_by(&mut a, 5, 35, Some(2), |x, y| y.cmp(x), &mut cnt);
+    assert_eq!(a, expect);
+}
+
+#[test]
+fn any_strategy_can_do_the_merging() {
+    let orig: Vec<i32> = (0..37).map(|i| (i * 11) % 37 - 18).collect();
+    let mut expect = orig.clone();
+    expect.sort_unstable();
+    for &strategy in MergeStrategy::ALL.iter() {
+        for &block in &[Some(1), Some(5), None] {
+            let mut a = orig.clone();
+            let merge = strategy.merge_fn();
+            bottom_up_merge_sort(&mut a, 0, 37, block, merge, &mut RealCounter::default());
+            assert_eq!(a, expect, "block={:?}, merge={}", block, strategy);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/*
+ * Run the command line tool with `stdin` piped in.
+ */
+fn cheap(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cheap"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run cheap");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn bad_input_fails_without_touching_stdout() {
+    let out = cheap(&["-o", "merge", "-i", "-", "--output", "-"], "[1,\"x\"]");
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty(), "{:?}", out.stdout);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Bad element"));
+
+    let out = cheap(&["-o", "merge", "-i", "-"], "[3, 1, 2]");
+    assert!(out.status.success());
+}
+
+#[test]
+fn zipf_exponent_is_checked() {
+    for zipf in ["nan", "inf", "-1", "-1e9", "x"] {
+        let arg = format!("--zipf={}", zipf);
+        let out = cheap(&["-o", "sort", "-a", "zipf", &arg], "");
+        assert_eq!(out.status.code(), Some(2), "{}", arg);
+        assert!(out.stdout.is_empty());
+    }
+    for zipf in ["0", "1.5", "1e3"] {
+        let out = cheap(&["-o", "sort", "-a", "zipf", "--zipf", zipf], "");
+        assert!(out.status.success(), "--zipf {}", zipf);
+    }
+}
+
+#[test]
+fn sweep_sizes_are_checked() {
+    let huge = usize::MAX.to_string();
+    for args in [
+        ["--min", huge.as_str(), "--max", huge.as_str()],
+        ["--min", "9", "--max", "15"],
+    ] {
+        let out = cheap(&[&["sweep", "-o", "merge"], &args[..]].concat(), "");
+        assert_eq!(out.status.code(), Some(2), "{:?}", args);
+        assert!(out.stdout.is_empty());
+    }
+
+    let out = cheap(&["sweep", "-o", "merge", "--min", "9", "--max", "16"], "");
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout).lines().count(), 3);
+}
+
+#[test]
+fn nth_past_the_end_is_a_usage_error() {
+    let out = cheap(&["-o", "select_nth", "--nth", "99", "-s", "10"], "");
+    assert_eq!(out.status.code(), Some(2));
+    assert!(out.stdout.is_empty());
+
+    let out = cheap(&["-o", "select_nth", "--nth", "9", "-s", "10"], "");
+    assert!(out.status.success());
+
+    // An input array is only measured once it's read.
+    let out = cheap(&["-o", "select_nth", "--nth", "3", "-i", "-"], "[3, 1, 2]");
+    assert_eq!(out.status.code(), Some(1));
+    let out = cheap(&["-o", "select_nth", "--nth", "2", "-i", "-"], "[3, 1, 2]");
+    assert!(out.status.success());
+}
+
+#[test]
+fn tracing_an_untraceable_op_fails() {
+    let path = std::env::temp_dir().join(format!("cheap-untraceable-{}.jsonl", std::process::id()));
+    let path = path.to_str().unwrap();
+    let out = cheap(&["-o", "sort", "-s", "20", "--trace", path], "");
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("can't be traced"));
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,63 @@
+use cheap::{Cheap, CheapOp, Counter, RealCounter};
+use json::JsonValue;
+
+#[test]
+fn nested_work_belongs_to_the_outermost_op() {
+    let mut cnt = RealCounter::default();
+    let params = (0, 0, 0);
+    cnt.enter(CheapOp::PopLeft, params, None);
+    cnt.count_compare();
+    cnt.enter(CheapOp::Recenter, params, None);
+    cnt.count_compare();
+    cnt.count_swap();
+    cnt.leave(CheapOp::Recenter, params);
+    cnt.count_swap();
+    cnt.leave(CheapOp::PopLeft, params);
+    cnt.enter(CheapOp::Recenter, params, None);
+    cnt.count_compare();
+    cnt.leave(CheapOp::Recenter, params);
+
+    let pop = cnt.ops[CheapOp::PopLeft as usize];
+    assert_eq!((pop.calls, pop.compares, pop.swaps), (1, 2, 2));
+    let rec = cnt.ops[CheapOp::Recenter as usize];
+    assert_eq!((rec.calls, rec.compares, rec.swaps), (1, 1, 0));
+}
+
+#[test]
+fn op_counts_and_other_add_up_to_the_totals() {
+    let mut a: Vec<i32> = (0..64).rev().collect();
+    let mut cnt = RealCounter::default();
+    {
+        let mut ch = Cheap::new_range(&mut a, 0, 0, 64, &mut cnt);
+        ch.recenter();
+        while ch.params().0 < ch.params().2 {
+            ch.pop_left();
+        }
+    }
+    // Work outside any operation goes to `other`.
+    cnt.count_compare();
+    cnt.count_swap();
+    assert_eq!(a, (0..64).collect::<Vec<_>>());
+
+    // The pops recentered on their own, but only the first recenter was called from outside.
+    assert!(cnt.recenters > 1);
+    assert_eq!(cnt.ops[CheapOp::Recenter as usize].calls, 1);
+    assert_eq!(cnt.ops[CheapOp::PopLeft as usize].calls, 64);
+
+    let compares: u64 = cnt.ops.iter().map(|o| o.compares).sum();
+    let swaps: u64 = cnt.ops.iter().map(|o| o.swaps).sum();
+    assert_eq!((compares + 1, swaps + 1), (cnt.compares, cnt.swaps));
+
+    let mut out = JsonValue::new_object();
+    cnt.copy_to(&mut out);
+    let ops = &out["ops"];
+    assert_eq!(ops["other"]["compares"], 1);
+    assert_eq!(ops["other"]["swaps"], 1);
+    let sum = |field: &str| -> u64 {
+        ops.entries()
+            .map(|(_, counts)| counts[field].as_u64().unwrap())
+            .sum()
+    };
+    assert_eq!(sum("compares"), cnt.compares);
+    assert_eq!(sum("swaps"), cnt.swaps);
+}
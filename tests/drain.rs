@@ -0,0 +1,99 @@
+use cheap::{Cheap, RealCounter};
+
+/*
+ * 0..n in a scrambled order, with every value repeated twice.
+ */
+fn scrambled(n: usize) -> Vec<usize> {
+    (0..2 * n).map(|i| (i * 7919) % n.max(1)).collect()
+}
+
+#[test]
+fn drain_left_yields_ascending_slots_in_order() {
+    for &n in &[0, 1, 2, 9, 100] {
+        let mut a = scrambled(n);
+        let mut expect = a.clone();
+        expect.sort_unstable();
+        let mut cnt = RealCounter::default();
+        let mut ch = Cheap::new_spanright(&mut a, &mut cnt);
+        ch.recenter();
+
+        let slots: Vec<usize> = ch.drain_left().collect();
+        assert_eq!(slots, (0..2 * n).collect::<Vec<_>>());
+        assert!(ch.is_empty());
+        assert_eq!(a, expect);
+    }
+}
+
+#[test]
+fn drain_right_yields_descending_slots_in_order() {
+    let mut a = scrambled(50);
+    let mut expect = a.clone();
+    expect.sort_unstable_by(|x, y| y.cmp(x));
+    let mut cnt = RealCounter::default();
+    let mut ch = Cheap::new_spanleft(&mut a, &mut cnt);
+    ch.recenter();
+
+    let drain = ch.drain_right();
+    assert_eq!(drain.len(), 100);
+    let slots: Vec<usize> = drain.collect();
+    assert_eq!(slots, (0..100).rev().collect::<Vec<_>>());
+    assert_eq!(a, expect);
+}
+
+#[test]
+fn dropping_a_drain_early_leaves_a_working_heap() {
+    let mut a = scrambled(40);
+    let mut cnt = RealCounter::default();
+    let mut ch = Cheap::new_spanright(&mut a, &mut cnt);
+    ch.recenter();
+
+    assert_eq!(ch.drain_left().take(30).count(), 30);
+    assert_eq!(ch.len(), 50);
+    assert_eq!(ch.drain_right().take(10).count(), 10);
+    assert_eq!(ch.params().0, 30);
+    assert_eq!(ch.len(), 40);
+    // Forty pops took 0..20 twice over.
+    assert_eq!(ch.peek(), Some(&20));
+
+    // What's left still drains in order, from either end.
+    let rest: Vec<usize> = ch.drain_left().collect();
+    assert_eq!(rest, (30..70).collect::<Vec<_>>());
+    assert!(a[..70].windows(2).all(|w| w[0] <= w[1]));
+    assert!(a[70..].windows(2).all(|w| w[0] >= w[1]));
+    // The right drain took the ten values between the two left drains.
+    assert!(a[29] <= a[79] && a[70] <= a[30]);
+}
+
+#[test]
+fn draining_an_empty_heap_yields_nothing() {
+    let mut a = vec![3, 1, 2];
+    let mut cnt = RealCounter::default();
+    let mut ch = Cheap::new_range(&mut a, 2, 2, 2, &mut cnt);
+    assert_eq!(ch.drain_left().len(), 0);
+    assert_eq!(ch.drain_right().next(), None);
+    assert_eq!(ch.into_sorted_iter().next(), None);
+    assert_eq!(a, vec![3, 1, 2]);
+    assert_eq!(cnt.compares + cnt.swaps, 0);
+}
+
+#[test]
+fn into_sorted_iter_yields_every_value_in_order() {
+    let mut a = scrambled(30);
+    let orig = a.clone();
+    let mut expect = a[10..60].to_vec();
+    expect.sort_unstable();
+    let mut cnt = RealCounter::default();
+    let mut ch = Cheap::new_range(&mut a, 10, 10, 60, &mut cnt);
+    ch.recenter();
+
+    let it = ch.into_sorted_iter();
+    assert_eq!(it.len(), 50);
+    let mut got = Vec::new();
+    for v in it {
+        got.push(*v);
+        *v += 1000;
+    }
+    assert_eq!(got, expect);
+    assert_eq!(a[..10], orig[..10]);
+    assert!(a[10..].iter().all(|&v| v >= 1000));
+}
@@ -0,0 +1,129 @@
+use cheap::{Cheap, CheapError, RealCounter};
+
+type Op = fn(&mut Cheap<i32, RealCounter>) -> Result<(), CheapError>;
+
+/*
+ * Run `op` on a c-heap over `a[lo..hi]`, centered at `lo`, of an ascending array, which makes a
+ * valid heap. Check it's refused with `err` and leaves the markers and the array alone.
+ */
+fn assert_refused(lo: usize, hi: usize, op: Op, err: CheapError) {
+    let mut a: Vec<i32> = (0..8).collect();
+    let orig = a.clone();
+    let mut cnt = RealCounter::default();
+    {
+        let mut ch = Cheap::new_range(&mut a, lo, lo, hi, &mut cnt);
+        assert_eq!(op(&mut ch), Err(err), "lo={}, hi={}", lo, hi);
+        assert_eq!(ch.params(), (lo, lo, hi));
+    }
+    assert_eq!(a, orig);
+    assert_eq!(cnt.swaps, 0);
+}
+
+#[test]
+fn pops_from_an_empty_heap_are_refused() {
+    let ops: [Op; 3] = [
+        |ch| ch.try_pop_left(),
+        |ch| ch.try_pop_right(),
+        |ch| ch.try_poppush(0),
+    ];
+    for op in ops.iter() {
+        assert_refused(3, 3, *op, CheapError::Empty);
+        assert_refused(0, 0, *op, CheapError::Empty);
+    }
+}
+
+#[test]
+fn moves_past_the_array_are_refused() {
+    let left: [Op; 3] = [
+        |ch| ch.try_push_left(),
+        |ch| ch.try_slide_left(),
+        |ch| ch.try_push_left_swap(7),
+    ];
+    for op in left.iter() {
+        assert_refused(0, 4, *op, CheapError::OutOfBounds);
+    }
+    for op in left[..2].iter() {
+        assert_refused(0, 8, *op, CheapError::OutOfBounds);
+    }
+
+    let right: [Op; 3] = [
+        |ch| ch.try_push_right(),
+        |ch| ch.try_slide_right(),
+        |ch| ch.try_push_right_swap(0),
+    ];
+    for op in right.iter() {
+        assert_refused(4, 8, *op, CheapError::OutOfBounds);
+    }
+    for op in right[..2].iter() {
+        assert_refused(0, 8, *op, CheapError::OutOfBounds);
+    }
+
+    let index: [Op; 2] = [|ch| ch.try_poppush(8), |ch| ch.try_pushpop(8)];
+    for op in index.iter() {
+        assert_refused(2, 5, *op, CheapError::OutOfBounds);
+    }
+}
+
+#[test]
+fn pushing_an_index_inside_the_heap_is_refused() {
+    let ops: [Op; 5] = [
+        |ch| ch.try_poppush(2),
+        |ch| ch.try_poppush(5),
+        |ch| ch.try_pushpop(3),
+        |ch| ch.try_push_left_swap(4),
+        |ch| ch.try_push_right_swap(2),
+    ];
+    for op in ops.iter() {
+        assert_refused(2, 6, *op, CheapError::IndexInsideHeap);
+    }
+}
+
+#[test]
+fn allowed_moves_still_succeed_at_the_edges() {
+    let mut a: Vec<i32> = (0..8).collect();
+    let mut cnt = RealCounter::default();
+    let mut ch = Cheap::new_range(&mut a, 1, 1, 7, &mut cnt);
+    // One step from either end of the array is still inside it.
+    assert_eq!(ch.try_push_left(), Ok(()));
+    assert_eq!(ch.try_push_right(), Ok(()));
+    assert_eq!(ch.params().0, 0);
+    assert_eq!(ch.params().2, 8);
+    assert_eq!(ch.try_push_left(), Err(CheapError::OutOfBounds));
+    assert_eq!(ch.try_pop_left(), Ok(()));
+    assert_eq!(ch.try_slide_left(), Ok(()));
+    let (lo, _, hi) = ch.params();
+    assert_eq!((lo, hi), (0, 7));
+}
+
+#[test]
+fn a_single_element_slides_left() {
+    let mut a = [0, 1, 2, 3];
+    let mut cnt = RealCounter::default();
+    let mut ch = Cheap::new_range(&mut a, 3, 3, 4, &mut cnt);
+    for lo in (0..3).rev() {
+        assert_eq!(ch.try_slide_left(), Ok(()));
+        assert_eq!(ch.params(), (lo, lo, lo + 1));
+    }
+    assert_eq!(ch.try_slide_left(), Err(CheapError::OutOfBounds));
+}
+
+#[test]
+#[should_panic(expected = "c-heap error: pop when empty")]
+fn panicking_ops_report_the_same_error() {
+    let mut a = [1, 2, 3];
+    let mut cnt = RealCounter::default();
+    let mut ch = Cheap::new_range(&mut a, 1, 1, 1, &mut cnt);
+    ch.pop_right();
+}
+#[test]
+fn removing_an_index_outside_the_heap_is_refused() {
+    let ops: [Op; 4] = [
+        |ch| ch.try_remove_left(1),
+        |ch| ch.try_remove_left(6),
+        |ch| ch.try_remove_right(0),
+        |ch| ch.try_remove_right(7),
+    ];
+    for op in ops.iter() {
+        assert_refused(2, 6, *op, CheapError::IndexOutsideHeap);
+    }
+}
@@ -0,0 +1,96 @@
+use cheap::{
+    heap_sort_left, heap_sort_left_with, merge_sort, merge_sort_with, Cheap, RealCounter, TotalCmp,
+    TotalOrd, TotalOrder,
+};
+use rand::prelude::{thread_rng, Rng, SliceRandom};
+
+/*
+ * Floats with plenty of NaNs of both signs, zeros of both signs, infinities and repeats.
+ */
+fn floats(n: usize) -> Vec<f64> {
+    let special = [
+        f64::NAN,
+        -f64::NAN,
+        0.0,
+        -0.0,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        1.5,
+        -1.5,
+    ];
+    let mut rng = thread_rng();
+    (0..n)
+        .map(|_| {
+            if rng.gen_bool(0.5) {
+                *special.choose(&mut rng).unwrap()
+            } else {
+                rng.gen_range(-100..100) as f64 / 4.0
+            }
+        })
+        .collect()
+}
+
+/*
+ * Check `a` is sorted: non-NaN values ascending with -0.0 before +0.0, then every NaN.
+ */
+fn assert_total_sorted(a: &[f64], orig: &[f64]) {
+    let mut expect = orig.to_vec();
+    expect.sort_by(TotalCmp::total_cmp);
+    assert_eq!(a.len(), expect.len());
+    for (x, y) in a.iter().zip(expect.iter()) {
+        assert!(
+            (x.is_nan() && y.is_nan()) || x.to_bits() == y.to_bits(),
+            "got {:?}, expected {:?}",
+            a,
+            expect
+        );
+    }
+}
+
+#[test]
+fn total_order_puts_nans_last_and_negative_zero_first() {
+    assert!(TotalOrd(-0.0f64) < TotalOrd(0.0));
+    assert!(TotalOrd(f64::INFINITY) < TotalOrd(f64::NAN));
+    assert!(TotalOrd(f64::INFINITY) < TotalOrd(-f64::NAN));
+    assert_eq!(TotalOrd(f64::NAN), TotalOrd(-f64::NAN));
+    assert!(TotalOrd(-1.0f32) < TotalOrd(f32::NAN));
+}
+
+#[test]
+fn merge_sort_handles_nan_and_signed_zero() {
+    for &n in &[0, 1, 2, 3, 8, 17, 100, 1000] {
+        let orig = floats(n);
+
+        let mut a = orig.clone();
+        merge_sort_with(
+            &mut a,
+            0,
+            n,
+            Cheap::merge_with,
+            &mut TotalOrder,
+            &mut RealCounter::default(),
+        );
+        assert_total_sorted(&a, &orig);
+
+        let mut w: Vec<TotalOrd<f64>> = orig.iter().copied().map(TotalOrd).collect();
+        merge_sort(&mut w, 0, n, Cheap::merge, &mut RealCounter::default());
+        let a: Vec<f64> = w.into_iter().map(|x| x.0).collect();
+        assert_total_sorted(&a, &orig);
+    }
+}
+
+#[test]
+fn heap_sort_left_handles_nan_and_signed_zero() {
+    for &n in &[0, 1, 2, 3, 8, 17, 100, 1000] {
+        let orig = floats(n);
+
+        let mut a = orig.clone();
+        heap_sort_left_with(&mut a, TotalOrder, &mut RealCounter::default());
+        assert_total_sorted(&a, &orig);
+
+        let mut w: Vec<TotalOrd<f64>> = orig.iter().copied().map(TotalOrd).collect();
+        heap_sort_left(&mut w, &mut RealCounter::default());
+        let a: Vec<f64> = w.into_iter().map(|x| x.0).collect();
+        assert_total_sorted(&a, &orig);
+    }
+}
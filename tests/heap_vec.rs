@@ -0,0 +1,129 @@
+use cheap::{ByOrdering, CenteredHeapVec, RealCounter};
+use rand::prelude::{thread_rng, Rng};
+
+/*
+ * Add to a model kept sorted in descending order, so its smallest value pops off the end.
+ */
+fn model_push(model: &mut Vec<i32>, v: i32) {
+    let at = model.partition_point(|&x| x > v);
+    model.insert(at, v);
+}
+
+#[test]
+fn mixed_pushes_and_pops_match_sorted_vec() {
+    let mut rng = thread_rng();
+    for &start in &[0, 1, 8, 33] {
+        let mut h = CenteredHeapVec::with_capacity(start);
+        let mut model = Vec::new();
+        // Lean on one end, then the other, so the heap runs out of room on both sides.
+        for phase in 0..8 {
+            let front = phase % 2 == 0;
+            for _ in 0..500 {
+                let r: f64 = rng.gen();
+                if r < 0.6 {
+                    let v = rng.gen_range(-100..100);
+                    if front == (r < 0.5) {
+                        h.push_front(v);
+                    } else {
+                        h.push_back(v);
+                    }
+                    model_push(&mut model, v);
+                } else if front {
+                    assert_eq!(h.pop_back(), model.pop());
+                } else {
+                    assert_eq!(h.pop_front(), model.pop());
+                }
+                assert_eq!(h.len(), model.len());
+                assert_eq!(h.peek(), model.last());
+                assert!(h.len() <= h.capacity());
+            }
+        }
+        while let Some(v) = h.pop_front() {
+            assert_eq!(Some(v), model.pop());
+        }
+        assert!(model.is_empty());
+    }
+}
+
+#[test]
+fn pops_on_empty_and_single_element_heaps() {
+    let mut h: CenteredHeapVec<i32> = CenteredHeapVec::new();
+    assert_eq!(h.pop_front(), None);
+    assert_eq!(h.pop_back(), None);
+    assert_eq!(h.peek(), None);
+
+    h.push_front(3);
+    assert_eq!(h.pop_back(), Some(3));
+    assert!(h.is_empty());
+    assert_eq!(h.pop_back(), None);
+
+    h.push_back(4);
+    assert_eq!(h.peek(), Some(&4));
+    assert_eq!(h.pop_front(), Some(4));
+    assert_eq!(h.pop_front(), None);
+
+    h.push_back(1);
+    h.push_front(2);
+    h.clear();
+    assert!(h.is_empty());
+    assert_eq!(h.pop_front(), None);
+}
+
+#[test]
+fn pushing_one_end_grows_and_recenters() {
+    let mut h = CenteredHeapVec::new();
+    for v in (0..1000).rev() {
+        h.push_front(v);
+    }
+    assert!(h.capacity() >= 1000);
+    for v in 1000..2000 {
+        h.push_back(v);
+    }
+    for v in 0..2000 {
+        let got = if v % 2 == 0 {
+            h.pop_front()
+        } else {
+            h.pop_back()
+        };
+        assert_eq!(got, Some(v));
+    }
+    assert!(h.is_empty());
+}
+
+#[test]
+fn walking_off_one_end_recenters_without_growing() {
+    // The heap never holds more than two, so running out of room only moves it back to the
+    // middle of the buffer.
+    let mut h = CenteredHeapVec::new();
+    h.push_back(0);
+    for v in 1..1000 {
+        h.push_back(v);
+        assert_eq!(h.pop_front(), Some(v - 1));
+    }
+    assert_eq!(h.capacity(), 8);
+
+    h.clear();
+    assert_eq!(h.capacity(), 8);
+    for v in 0..5 {
+        h.push_front(v);
+    }
+    assert_eq!(h.pop_back(), Some(0));
+}
+
+#[test]
+fn custom_orders_and_owned_values() {
+    let mut h = CenteredHeapVec::with_parts(
+        0,
+        ByOrdering(|a: &String, b: &String| b.cmp(a)),
+        RealCounter::default(),
+    );
+    for w in "one two three four five six".split(' ') {
+        h.push_back(w.to_string());
+    }
+    let mut got = Vec::new();
+    while let Some(w) = h.pop_front() {
+        got.push(w);
+    }
+    assert_eq!(got, vec!["two", "three", "six", "one", "four", "five"]);
+    assert!(h.counter().compares > 0);
+}
@@ -0,0 +1,98 @@
+use cheap::{NaturalOrder, RealCounter, SlidingMedian};
+use rand::prelude::{thread_rng, Rng};
+use std::collections::VecDeque;
+
+/*
+ * The middle elements of the window found by sorting a copy of it, lower first.
+ */
+fn naive_median_pair(window: &VecDeque<i32>) -> Option<(i32, i32)> {
+    let mut w: Vec<i32> = window.iter().copied().collect();
+    w.sort_unstable();
+    let n = w.len();
+    match n {
+        0 => None,
+        _ if n % 2 == 1 => Some((w[n / 2], w[n / 2])),
+        _ => Some((w[n / 2 - 1], w[n / 2])),
+    }
+}
+
+fn check(sm: &SlidingMedian<i32, NaturalOrder, RealCounter>, window: &VecDeque<i32>) {
+    let expect = naive_median_pair(window);
+    let got = sm.median_pair().map(|(a, b)| (*a, *b));
+    assert_eq!(got, expect, "window={:?}", window);
+    assert_eq!(sm.median().copied(), expect.map(|p| p.0));
+    assert_eq!(sm.len(), window.len());
+}
+
+#[test]
+fn fixed_width_matches_naive() {
+    let mut rng = thread_rng();
+    for &width in &[1, 2, 3, 4, 7, 16, 33] {
+        for &range in &[3, 50, 1000] {
+            let mut sm = SlidingMedian::with_parts(width, NaturalOrder, RealCounter::default());
+            let mut window = VecDeque::new();
+            for _ in 0..500 {
+                let v = rng.gen_range(0..range);
+                sm.push(v);
+                window.push_back(v);
+                if window.len() > width {
+                    assert_eq!(sm.evict_oldest(), window.pop_front());
+                }
+                check(&sm, &window);
+            }
+            assert!(sm.counter().compares > 0);
+        }
+    }
+}
+
+#[test]
+fn random_pushes_and_evictions_match_naive() {
+    let mut rng = thread_rng();
+    for _ in 0..50 {
+        let mut sm = SlidingMedian::with_parts(0, NaturalOrder, RealCounter::default());
+        let mut window = VecDeque::new();
+        for _ in 0..400 {
+            if rng.gen_bool(0.45) {
+                assert_eq!(sm.evict_oldest(), window.pop_front());
+            } else {
+                let v = rng.gen_range(-20..20);
+                sm.push(v);
+                window.push_back(v);
+            }
+            check(&sm, &window);
+        }
+    }
+}
+
+#[test]
+fn empty_window_has_no_median() {
+    let mut sm: SlidingMedian<i32> = SlidingMedian::new();
+    assert_eq!(sm.median(), None);
+    assert_eq!(sm.evict_oldest(), None);
+    sm.push(5);
+    assert_eq!(sm.median(), Some(&5));
+    assert_eq!(sm.evict_oldest(), Some(5));
+    assert!(sm.is_empty());
+}
+
+#[test]
+fn the_oldest_can_leave_from_either_half_or_the_middle() {
+    // Oldest is the low center, then in the high half, then deep in the low half.
+    let mut sm: SlidingMedian<i32> = SlidingMedian::new();
+    for &v in &[5, 1, 9] {
+        sm.push(v);
+    }
+    assert_eq!(sm.median(), Some(&5));
+    assert_eq!(sm.evict_oldest(), Some(5));
+    assert_eq!(sm.median_pair(), Some((&1, &9)));
+    sm.push(0);
+    assert_eq!(sm.median(), Some(&1));
+    assert_eq!(sm.evict_oldest(), Some(1));
+    assert_eq!(sm.evict_oldest(), Some(9));
+    assert_eq!(sm.median(), Some(&0));
+    for v in (10..16).rev() {
+        sm.push(v);
+    }
+    assert_eq!(sm.evict_oldest(), Some(0));
+    assert_eq!(sm.median_pair(), Some((&12, &13)));
+}
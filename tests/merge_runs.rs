@@ -0,0 +1,109 @@
+use cheap::{merge_runs, merge_runs_by, merge_runs_by_key, RealCounter};
+use std::process::Command;
+
+fn merged(mut a: Vec<i32>, bounds: &[usize]) -> Vec<i32> {
+    merge_runs(&mut a, bounds, &mut RealCounter::default());
+    a
+}
+
+#[test]
+fn no_bounds_leaves_one_sorted_run_alone() {
+    let mut cnt = RealCounter::default();
+    let mut a = vec![1, 2, 2, 7];
+    merge_runs(&mut a, &[], &mut cnt);
+    assert_eq!(a, vec![1, 2, 2, 7]);
+    assert_eq!(cnt.swaps, 0);
+
+    assert!(merged(vec![], &[]).is_empty());
+    assert!(merged(vec![], &[0, 0]).is_empty());
+}
+
+#[test]
+fn empty_runs_and_cuts_at_the_ends() {
+    assert_eq!(
+        merged(vec![3, 8, 1, 2, 9, 0, 4], &[2, 2, 5, 7, 7]),
+        vec![0, 1, 2, 3, 4, 8, 9]
+    );
+    assert_eq!(merged(vec![4, 5, 1, 2], &[0, 0, 2]), vec![1, 2, 4, 5]);
+}
+
+#[test]
+fn every_element_its_own_run() {
+    let a: Vec<i32> = (0..40).map(|i| (i * 13) % 40).collect();
+    let bounds: Vec<usize> = (1..40).collect();
+    assert_eq!(merged(a, &bounds), (0..40).collect::<Vec<_>>());
+}
+
+#[test]
+fn later_runs_can_be_wholly_smaller() {
+    // Runs in reverse order of their values, so the first run is the last to give anything up.
+    let a: Vec<i32> = (0..4).rev().flat_map(|r| r * 5..r * 5 + 5).collect();
+    assert_eq!(merged(a, &[5, 10, 15]), (0..20).collect::<Vec<_>>());
+
+    let mut a = vec![9, 5, 1, 8, 2];
+    merge_runs_by(
+        &mut a,
+        &[0, 3, 3],
+        |x, y| y.cmp(x),
+        &mut RealCounter::default(),
+    );
+    assert_eq!(a, vec![9, 8, 5, 2, 1]);
+
+    let mut a = vec!["ccc", "dddd", "a", "bb", "eeeee"];
+    merge_runs_by_key(&mut a, &[2, 4], |s| s.len(), &mut RealCounter::default());
+    assert_eq!(a, vec!["a", "bb", "ccc", "dddd", "eeeee"]);
+}
+
+#[test]
+#[should_panic(expected = "bounds must ascend")]
+fn descending_bounds_are_refused() {
+    merged(vec![1, 2, 3, 4], &[3, 1]);
+}
+
+/*
+ * Run the command line tool and parse the report it prints.
+ */
+fn cheap_report(args: &[&str]) -> json::JsonValue {
+    let out = Command::new(env!("CARGO_BIN_EXE_cheap"))
+        .args(args)
+        .output()
+        .expect("failed to run cheap");
+    assert!(out.status.success(), "cheap {:?} failed", args);
+    json::parse(&String::from_utf8(out.stdout).unwrap()).unwrap()
+}
+
+#[test]
+fn cli_reports_the_pairwise_merge_beside_the_one_pass_merge() {
+    let out = cheap_report(&["-o", "merge_runs", "-c", "-s", "300", "-k", "6"]);
+    assert_eq!(out["runs"], 6);
+    assert_eq!(out["is_sorted"], true);
+    assert!(out["compares"].as_u64().unwrap() > 0);
+    assert!(out["pairwise_compares"].as_u64().unwrap() > 0);
+
+    // A single run has nothing to merge either way.
+    let out = cheap_report(&["-o", "merge_runs", "-c", "-s", "50", "-k", "1"]);
+    assert_eq!(out["compares"], 0);
+    assert_eq!(out["pairwise_compares"], 0);
+    assert_eq!(out["pairwise_swaps"], 0);
+}
+
+#[test]
+fn cli_names_the_pairwise_strategy() {
+    for &merge in &["cheap", "sym_merge", "block_swap", "buffered"] {
+        let args = [
+            "-o",
+            "merge_runs",
+            "-c",
+            "-s",
+            "200",
+            "-k",
+            "5",
+            "--merge",
+            merge,
+        ];
+        let out = cheap_report(&args);
+        assert_eq!(out["merge"], merge);
+        assert_eq!(out["is_sorted"], true);
+        assert!(out["pairwise_compares"].as_u64().unwrap() > 0, "{}", merge);
+    }
+}
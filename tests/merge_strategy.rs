@@ -0,0 +1,89 @@
+use cheap::{ByKey, MergeStrategy, RealCounter};
+
+/*
+ * `len` values from `0..range`, with `a[lo..md]` and `a[md..hi]` each sorted.
+ */
+fn two_runs(len: usize, lo: usize, md: usize, hi: usize, range: u32) -> Vec<u32> {
+    let mut a: Vec<u32> = (0..len as u32).map(|i| (i * 7919 + 13) % range).collect();
+    a[lo..md].sort_unstable();
+    a[md..hi].sort_unstable();
+    a
+}
+
+#[test]
+fn every_strategy_handles_the_awkward_splits() {
+    let cases: [(Vec<u32>, usize); 7] = [
+        (vec![], 0),
+        (vec![1, 2, 3], 0),
+        (vec![1, 2, 3], 3),
+        (vec![5, 0], 1),
+        (vec![4, 5, 6, 7, 0, 1, 2], 4),
+        (vec![0, 2, 4, 6, 1, 3, 5, 7], 4),
+        (vec![3, 3, 3, 1, 3, 3], 3),
+    ];
+    for &strategy in MergeStrategy::ALL.iter() {
+        let merge = strategy.merge_fn();
+        for (orig, md) in cases.iter() {
+            let mut a = orig.clone();
+            let n = a.len();
+            merge(&mut a, 0, *md, n, &mut RealCounter::default());
+            let mut expect = orig.clone();
+            expect.sort_unstable();
+            assert_eq!(a, expect, "{}: {:?} at {}", strategy, orig, md);
+        }
+
+        // Only `a[lo..hi]` may change.
+        let mut a = two_runs(30, 4, 12, 25, 10);
+        let mut expect = a.clone();
+        expect[4..25].sort_unstable();
+        merge(&mut a, 4, 12, 25, &mut RealCounter::default());
+        assert_eq!(a, expect, "{}", strategy);
+    }
+}
+
+#[test]
+fn rotation_and_buffered_merges_are_stable() {
+    type Tagged = (u32, usize);
+    let key: fn(&Tagged) -> u32 = |p| p.0;
+    for &strategy in &[
+        MergeStrategy::SymMerge,
+        MergeStrategy::BlockSwap,
+        MergeStrategy::Buffered,
+    ] {
+        let merge = strategy.merge_with_fn::<Tagged, ByKey<fn(&Tagged) -> u32>, RealCounter>();
+        for &md in &[0, 1, 20, 49, 50] {
+            let mut a: Vec<Tagged> = two_runs(50, 0, md, 50, 4)
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (v, i))
+                .collect();
+            let mut expect = a.clone();
+            expect.sort_by_key(|p| p.0);
+            let mut cnt = RealCounter::default();
+            merge(&mut a, 0, md, 50, &mut ByKey(key), &mut cnt);
+            assert_eq!(a, expect, "{}: md={}", strategy, md);
+        }
+    }
+}
+
+#[test]
+fn buffered_merge_stays_within_its_bounds() {
+    let merge = MergeStrategy::Buffered.merge_fn();
+    for md in 0..=40 {
+        let mut a = two_runs(40, 0, md, 40, 1000);
+        let mut cnt = RealCounter::default();
+        merge(&mut a, 0, md, 40, &mut cnt);
+        assert!(a.windows(2).all(|w| w[0] <= w[1]));
+        assert!(cnt.compares <= 39, "md={}: {} compares", md, cnt.compares);
+        assert!(cnt.swaps <= 40, "md={}: {} swaps", md, cnt.swaps);
+    }
+}
+
+#[test]
+fn names_round_trip() {
+    for &strategy in MergeStrategy::ALL.iter() {
+        assert_eq!(MergeStrategy::from_name(strategy.name()), Some(strategy));
+        assert_eq!(strategy.to_string(), strategy.name());
+    }
+    assert_eq!(MergeStrategy::from_name("quick"), None);
+}
@@ -0,0 +1,98 @@
+use cheap::{natural_merge_sort, natural_merge_sort_by_key, Cheap, RealCounter};
+
+fn sorted(mut a: Vec<i32>) -> Vec<i32> {
+    let n = a.len();
+    natural_merge_sort(&mut a, 0, n, &mut RealCounter::default());
+    a
+}
+
+#[test]
+fn tiny_inputs() {
+    assert!(sorted(vec![]).is_empty());
+    assert_eq!(sorted(vec![7]), vec![7]);
+    assert_eq!(sorted(vec![2, 1]), vec![1, 2]);
+    assert_eq!(sorted(vec![1, 1, 1]), vec![1, 1, 1]);
+}
+
+#[test]
+fn descending_runs_with_ties_are_not_reversed_whole() {
+    // `5, 5` isn't strictly descending, so the run has to stop there rather than flip it.
+    let a = vec![9, 7, 5, 5, 3, 1, 1, 0, 8, 8, 2];
+    let mut expect = a.clone();
+    expect.sort_unstable();
+    assert_eq!(sorted(a), expect);
+
+    let mut a: Vec<i32> = (0..200).map(|i| (199 - i) / 4).collect();
+    let expect: Vec<i32> = (0..200).map(|i| i / 4).collect();
+    assert_eq!(sorted(a.clone()), expect);
+    a.reverse();
+    assert_eq!(sorted(a), expect);
+}
+
+#[test]
+fn runs_of_every_shape() {
+    // Up then down, short runs that need padding, and long flat stretches.
+    let organ: Vec<i32> = (0..100).chain((0..100).rev()).collect();
+    let mut expect = organ.clone();
+    expect.sort_unstable();
+    assert_eq!(sorted(organ), expect);
+
+    let saw: Vec<i32> = (0..300).map(|i| i % 3).collect();
+    let mut expect = saw.clone();
+    expect.sort_unstable();
+    assert_eq!(sorted(saw), expect);
+
+    let steps: Vec<i32> = (0..300)
+        .map(|i| (i / 50) * if i % 100 < 50 { 1 } else { -1 })
+        .collect();
+    let mut expect = steps.clone();
+    expect.sort_unstable();
+    assert_eq!(sorted(steps), expect);
+}
+
+#[test]
+fn two_long_runs_take_one_merge() {
+    let a: Vec<i32> = (0..200)
+        .map(|i| 2 * i)
+        .chain((0..200).map(|i| 2 * i + 1))
+        .collect();
+    let mut b = a.clone();
+    let mut cnt = RealCounter::default();
+    natural_merge_sort(&mut b, 0, 400, &mut cnt);
+    assert_eq!(b, (0..400).collect::<Vec<_>>());
+
+    let mut c = a;
+    let mut merge_cnt = RealCounter::default();
+    Cheap::merge(&mut c, 0, 200, 400, &mut merge_cnt);
+    // Finding the runs costs one compare per element on top of the merge itself.
+    assert_eq!(cnt.compares, merge_cnt.compares + 399);
+    assert_eq!(cnt.swaps, merge_cnt.swaps);
+}
+
+#[test]
+fn sorted_input_takes_one_pass() {
+    let mut a: Vec<u32> = (0..500).collect();
+    let mut cnt = RealCounter::default();
+    natural_merge_sort(&mut a, 0, 500, &mut cnt);
+    assert!(a.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(cnt.compares, 499);
+    assert_eq!(cnt.swaps, 0);
+
+    let mut a: Vec<u32> = (0..500).rev().collect();
+    let mut cnt = RealCounter::default();
+    natural_merge_sort(&mut a, 0, 500, &mut cnt);
+    assert_eq!(a, (0..500).collect::<Vec<_>>());
+    assert_eq!(cnt.compares, 499);
+    assert_eq!(cnt.swaps, 250);
+}
+
+#[test]
+fn sorts_only_the_subrange() {
+    let mut a = b"natural merge sort".to_vec();
+    let n = a.len();
+    let mut expect = a.clone();
+    expect[3..n - 2].sort_by(|x, y| y.cmp(x));
+    let mut cnt = RealCounter::default();
+    natural_merge_sort_by_key(&mut a, 3, n - 2, |&b| std::cmp::Reverse(b), &mut cnt);
+    assert_eq!(a, expect);
+}
@@ -0,0 +1,224 @@
+use cheap::{
+    running_sort_left, running_sort_stream, CheapError, CheapOp, DummyCounter, RealCounter,
+    RingCheap,
+};
+use rand::prelude::{thread_rng, Rng};
+
+/*
+ * The values inside the ring, sorted.
+ */
+fn contents(ring: &RingCheap<i32, DummyCounter>) -> Vec<i32> {
+    let a = ring.as_slice();
+    let mut v: Vec<i32> = (0..a.len())
+        .filter(|&i| ring.contains(i))
+        .map(|i| a[i])
+        .collect();
+    v.sort_unstable();
+    v
+}
+
+#[test]
+fn random_ops_across_the_wrap_keep_invariants() {
+    let mut rng = thread_rng();
+    for cap in 1..=11 {
+        for _ in 0..20 {
+            let mut buf: Vec<i32> = (0..cap).map(|_| rng.gen_range(0..20)).collect();
+            let mut cnt = DummyCounter {};
+            let mut ring = RingCheap::new(&mut buf, &mut cnt);
+            let mut model: Vec<i32> = Vec::new();
+            for _ in 0..300 {
+                let full = ring.len() == cap;
+                match rng.gen_range(0..8) {
+                    0 | 1 if !full => {
+                        let v = rng.gen_range(0..20);
+                        if rng.gen_bool(0.5) {
+                            ring.insert_left(v);
+                        } else {
+                            ring.insert_right(v);
+                        }
+                        model.push(v);
+                    }
+                    2 | 3 if !ring.is_empty() => {
+                        let best = *ring.peek().unwrap();
+                        let i = if rng.gen_bool(0.5) {
+                            ring.pop_left()
+                        } else {
+                            ring.pop_right()
+                        };
+                        assert!(!ring.contains(i));
+                        assert_eq!(ring.as_slice()[i], best);
+                        model.sort_unstable();
+                        assert_eq!(model.remove(0), best);
+                    }
+                    4 if !full => ring.slide_right(),
+                    5 if !full => ring.slide_left(),
+                    6 | 7 if !full && !ring.is_empty() => {
+                        let outside: Vec<usize> = (0..cap).filter(|&i| !ring.contains(i)).collect();
+                        let i = outside[rng.gen_range(0..outside.len())];
+                        let best = *ring.peek().unwrap();
+                        model.push(ring.as_slice()[i]);
+                        model.sort_unstable();
+                        if rng.gen_bool(0.5) {
+                            // Pops the best regardless of what comes in.
+                            ring.poppush(i);
+                            let at = model.iter().position(|&v| v == best).unwrap();
+                            model.remove(at);
+                            assert_eq!(ring.as_slice()[i], best);
+                        } else {
+                            // Pops whichever of the two is better.
+                            ring.pushpop(i);
+                            assert_eq!(model.remove(0), ring.as_slice()[i]);
+                        }
+                    }
+                    _ => continue,
+                }
+                ring.check();
+                model.sort_unstable();
+                assert_eq!(contents(&ring), model);
+                assert_eq!(ring.peek(), model.first());
+            }
+        }
+    }
+}
+
+#[test]
+fn slides_carry_the_heap_round_the_ring() {
+    let mut buf: Vec<i32> = (0..7).rev().collect();
+    let mut cnt = DummyCounter {};
+    let mut ring = RingCheap::new(&mut buf, &mut cnt);
+    for _ in 0..4 {
+        ring.push_right();
+    }
+    for _ in 0..30 {
+        ring.slide_right();
+        ring.check();
+        assert_eq!(ring.len(), 4);
+    }
+    for _ in 0..45 {
+        ring.slide_left();
+        ring.check();
+        assert_eq!(ring.len(), 4);
+    }
+}
+
+#[test]
+fn stream_sort_matches_slice_sort() {
+    let mut rng = thread_rng();
+    for run in 1..=11 {
+        for cap in run..=11 {
+            for &n in &[0, 1, run, 3 * cap + 1, 100] {
+                let input: Vec<i32> = (0..n).map(|_| rng.gen_range(0..50)).collect();
+                let mut expect = input.clone();
+                running_sort_left(&mut expect, run, &mut RealCounter::default());
+
+                let mut buf = vec![0; cap];
+                let mut got = Vec::new();
+                running_sort_stream(
+                    &mut buf,
+                    run,
+                    input.iter().copied(),
+                    |v| got.push(*v),
+                    &mut RealCounter::default(),
+                );
+                assert_eq!(got, expect, "run={}, cap={}, input={:?}", run, cap, input);
+            }
+        }
+    }
+}
+
+#[test]
+fn inserts_wrap_and_hand_back_the_old_slot() {
+    let mut buf = vec![10, 11, 12, 13];
+    let mut cnt = DummyCounter {};
+    let mut ring = RingCheap::new(&mut buf, &mut cnt);
+    // An empty ring at slot 0 pushes left into the last slot, and right into the first.
+    assert_eq!(ring.insert_left(5), 13);
+    assert_eq!(ring.insert_right(7), 10);
+    assert_eq!(ring.params().0, 3);
+    assert!(ring.contains(3) && ring.contains(0));
+    assert!(!ring.contains(1) && !ring.contains(2));
+
+    assert_eq!(ring.pop_left(), 3);
+    assert_eq!(ring.as_slice()[3], 5);
+    assert_eq!(ring.pop_right(), 0);
+    assert_eq!(ring.as_slice()[0], 7);
+    assert!(ring.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "slide a full ring")]
+fn a_full_ring_cannot_slide() {
+    let mut buf = vec![3, 1, 2];
+    let mut cnt = DummyCounter {};
+    let mut ring = RingCheap::new(&mut buf, &mut cnt);
+    for _ in 0..3 {
+        ring.push_right();
+    }
+    assert_eq!(ring.peek(), Some(&1));
+    ring.slide_right();
+}
+
+#[test]
+fn an_empty_slice_is_an_empty_ring() {
+    let mut buf: Vec<i32> = Vec::new();
+    let mut cnt = DummyCounter {};
+    let mut ring = RingCheap::new(&mut buf, &mut cnt);
+    assert!(!ring.contains(0));
+    assert_eq!(ring.params(), (0, 0, 0));
+    assert_eq!(ring.peek(), None);
+    assert_eq!(ring.try_pop_left(), Err(CheapError::Empty));
+    assert_eq!(ring.try_push_right(), Err(CheapError::Full));
+    assert_eq!(ring.try_slide_left(), Err(CheapError::Full));
+}
+
+#[test]
+fn refused_ops_leave_the_ring_alone() {
+    let mut buf = vec![4, 9, 2, 7, 5];
+    let mut cnt = DummyCounter {};
+    let mut ring = RingCheap::new(&mut buf, &mut cnt);
+    assert_eq!(ring.try_pop_right(), Err(CheapError::Empty));
+    assert_eq!(ring.try_poppush(1), Err(CheapError::Empty));
+    for _ in 0..3 {
+        ring.slide_left();
+        ring.push_left();
+    }
+    // Three elements wrapped around slot 0, with two free slots left.
+    let before = (ring.params(), ring.as_slice().to_vec());
+    assert_eq!(
+        ring.try_poppush(before.0 .0),
+        Err(CheapError::IndexInsideHeap)
+    );
+    assert_eq!(ring.try_pushpop(5), Err(CheapError::OutOfBounds));
+    assert_eq!((ring.params(), ring.as_slice().to_vec()), before);
+
+    ring.push_right();
+    ring.push_left();
+    assert_eq!(ring.try_push_left(), Err(CheapError::Full));
+    assert_eq!(ring.try_slide_right(), Err(CheapError::Full));
+    let lo = ring.params().0;
+    assert_eq!(ring.try_pop_left(), Ok(lo));
+}
+
+#[test]
+fn ring_ops_are_counted_as_operations() {
+    let mut buf: Vec<i32> = (0..16).rev().collect();
+    let mut cnt = RealCounter::default();
+    let mut ring = RingCheap::new(&mut buf, &mut cnt);
+    for _ in 0..10 {
+        ring.push_right();
+    }
+    for _ in 0..20 {
+        ring.slide_right();
+    }
+    while !ring.is_empty() {
+        ring.pop_left();
+    }
+    let ops = &cnt.ops;
+    assert_eq!(ops[CheapOp::PushRight as usize].calls, 10);
+    assert_eq!(ops[CheapOp::SlideRight as usize].calls, 20);
+    assert_eq!(ops[CheapOp::PopLeft as usize].calls, 10);
+    assert!(cnt.recenters > 0);
+    assert!(!cnt.sift_in_levels.is_empty() && !cnt.sift_out_levels.is_empty());
+    let counted: u64 = ops.iter().map(|o| o.compares).sum();
+    assert_eq!(counted, cnt.compares);
+}
@@ -0,0 +1,60 @@
+use cheap::{is_selected, select_nth, select_nth_by, RealCounter};
+use rand::prelude::{thread_rng, SliceRandom};
+
+#[test]
+fn select_nth_finds_every_rank_from_either_end() {
+    let mut rng = thread_rng();
+    for &n in &[1, 2, 3, 8, 101] {
+        // Each value three times, so ties straddle the selected rank.
+        let mut base: Vec<usize> = (0..n).map(|i| i / 3).collect();
+        base.shuffle(&mut rng);
+        let mut sorted = base.clone();
+        sorted.sort_unstable();
+        for (nth, expect) in sorted.iter().enumerate() {
+            let mut a = base.clone();
+            let mut cnt = RealCounter::default();
+            let got = *select_nth(&mut a, nth, &mut cnt);
+            assert_eq!(got, *expect, "n={}, nth={}", n, nth);
+            assert!(is_selected(&a, nth), "n={}, nth={}: {:?}", n, nth, a);
+        }
+    }
+}
+
+#[test]
+fn select_nth_by_follows_the_comparator() {
+    let mut a: Vec<i32> = (-20..20).collect();
+    a.shuffle(&mut thread_rng());
+    let got = *select_nth_by(&mut a, 0, |x, y| y.cmp(x), &mut RealCounter::default());
+    assert_eq!(got, 19);
+    let got = *select_nth_by(&mut a, 39, |x, y| y.cmp(x), &mut RealCounter::default());
+    assert_eq!(got, -20);
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn select_nth_past_the_end_panics() {
+    let mut a = vec![1, 2, 3];
+    select_nth(&mut a, 3, &mut RealCounter::default());
+}
+
+#[test]
+fn is_selected_rejects_misplaced_neighbours() {
+    assert!(is_selected(&[1, 0, 2, 5, 3], 2));
+    assert!(!is_selected(&[1, 3, 2, 5, 0], 2));
+    assert!(!is_selected(&[1, 0, 2], 3));
+    assert!(!is_selected::<u8>(&[], 0));
+}
+
+#[test]
+fn ranks_near_an_end_cost_less_than_the_median() {
+    // Only the side nearer `nth` gets popped, so the extremes are cheapest.
+    let base: Vec<u32> = (0..1000).map(|i| (i * 389) % 1000).collect();
+    let cost = |nth| {
+        let mut a = base.clone();
+        let mut cnt = RealCounter::default();
+        assert_eq!(*select_nth(&mut a, nth, &mut cnt), nth as u32);
+        cnt.compares
+    };
+    assert!(cost(0) < cost(500));
+    assert!(cost(999) < cost(500));
+}
@@ -0,0 +1,150 @@
+use cheap::{
+    heap_sort_left_by, heap_sort_left_by_key, heap_sort_right_by, heap_sort_right_by_key,
+    heap_sort_right_with, merge_sort_by, merge_sort_by_key, running_sort_left,
+    running_sort_left_by, running_sort_right, running_sort_right_by_key, ByOrdering, Cheap,
+    RealCounter,
+};
+use std::cell::Cell;
+use std::cmp::{Ordering, Reverse};
+
+const WORDS: [&str; 12] = [
+    "merge", "heap", "sift", "pop", "push", "slide", "center", "a", "run", "key", "by", "order",
+];
+
+#[test]
+fn reverse_comparators_sort_descending() {
+    let expect: Vec<i32> = (0..50).rev().collect();
+    let scrambled = || (0..50).map(|i| (i * 17) % 50).collect::<Vec<i32>>();
+
+    let mut a = scrambled();
+    merge_sort_by(&mut a, 0, 50, |x, y| y.cmp(x), &mut RealCounter::default());
+    assert_eq!(a, expect);
+
+    let mut a = scrambled();
+    heap_sort_left_by(&mut a, |x, y| y.cmp(x), &mut RealCounter::default());
+    assert_eq!(a, expect);
+
+    let mut a = scrambled();
+    heap_sort_right_by(&mut a, |x, y| y.cmp(x), &mut RealCounter::default());
+    assert_eq!(a, expect);
+}
+
+#[test]
+fn keys_sort_by_the_extracted_value() {
+    let mut by_len: Vec<usize> = WORDS.iter().map(|w| w.len()).collect();
+    by_len.sort_unstable();
+
+    let mut a = WORDS.to_vec();
+    let n = a.len();
+    merge_sort_by_key(&mut a, 0, n, |w| w.len(), &mut RealCounter::default());
+    assert_eq!(a.iter().map(|w| w.len()).collect::<Vec<_>>(), by_len);
+
+    let mut a = WORDS.to_vec();
+    heap_sort_left_by_key(&mut a, |w| w.len(), &mut RealCounter::default());
+    assert_eq!(a.iter().map(|w| w.len()).collect::<Vec<_>>(), by_len);
+
+    // Keys can rank on anything, here the last letter backwards.
+    let mut a = WORDS.to_vec();
+    heap_sort_right_by_key(
+        &mut a,
+        |w| Reverse(w.bytes().last()),
+        &mut RealCounter::default(),
+    );
+    assert!(a
+        .windows(2)
+        .all(|p| p[0].bytes().last() >= p[1].bytes().last()));
+}
+
+#[test]
+fn merge_by_respects_the_comparator_at_the_split() {
+    // Both halves are sorted descending; the split puts a whole run on each side.
+    let mut cnt = RealCounter::default();
+    let mut a = vec![9, 7, 5, 3, 8, 6, 4, 2];
+    Cheap::merge_by(&mut a, 0, 4, 8, |x: &i32, y| y.cmp(x), &mut cnt);
+    assert_eq!(a, vec![9, 8, 7, 6, 5, 4, 3, 2]);
+
+    let mut a = vec!["bb", "dddd", "a", "ccc"];
+    Cheap::merge_by_key(&mut a, 0, 2, 4, |s: &&str| s.len(), &mut cnt);
+    assert_eq!(a, vec!["a", "bb", "ccc", "dddd"]);
+}
+
+/*
+ * Check a comparator called `calls` times against the compares counted. Debug builds also check
+ * the heap as they go, which calls the comparator without counting it.
+ */
+fn assert_calls_counted(calls: u64, cnt: &RealCounter) {
+    assert!(cnt.compares > 0);
+    if cfg!(debug_assertions) {
+        assert!(calls >= cnt.compares, "{} < {}", calls, cnt.compares);
+    } else {
+        assert_eq!(calls, cnt.compares);
+    }
+}
+
+#[test]
+fn every_comparator_call_is_counted() {
+    let calls = Cell::new(0u64);
+    let counting = |x: &u32, y: &u32| {
+        calls.set(calls.get() + 1);
+        x.cmp(y)
+    };
+
+    let mut a: Vec<u32> = (0..200).rev().collect();
+    let mut cnt = RealCounter::default();
+    heap_sort_right_with(&mut a, ByOrdering(counting), &mut cnt);
+    assert_eq!(a, (0..200).collect::<Vec<_>>());
+    assert_calls_counted(calls.replace(0), &cnt);
+
+    let mut a: Vec<u32> = (0..200).map(|i| (i * 73) % 200).collect();
+    let mut cnt = RealCounter::default();
+    heap_sort_left_by(&mut a, counting, &mut cnt);
+    assert_eq!(a, (0..200).collect::<Vec<_>>());
+    assert_calls_counted(calls.replace(0), &cnt);
+
+    let mut a: Vec<u32> = (0..100)
+        .map(|i| 2 * i)
+        .chain((0..100).map(|i| 2 * i + 1))
+        .collect();
+    let mut cnt = RealCounter::default();
+    Cheap::merge_with(&mut a, 0, 100, 200, &mut ByOrdering(counting), &mut cnt);
+    assert_eq!(a, (0..200).collect::<Vec<_>>());
+    assert_calls_counted(calls.replace(0), &cnt);
+}
+
+#[test]
+fn running_sorts_by_match_the_natural_sort_of_mapped_values() {
+    let orig: Vec<i32> = (0..120).map(|i| (i * 37) % 61 - 30).collect();
+    for run in 1..8 {
+        // Sorting by a reversed comparator is sorting the negated values.
+        let mut a = orig.clone();
+        running_sort_left_by(&mut a, run, |x, y| y.cmp(x), &mut RealCounter::default());
+        let mut negated: Vec<i32> = orig.iter().map(|v| -v).collect();
+        running_sort_left(&mut negated, run, &mut RealCounter::default());
+        assert_eq!(
+            a,
+            negated.iter().map(|v| -v).collect::<Vec<_>>(),
+            "run={}",
+            run
+        );
+
+        // Sorting by a key is sorting the keys, when the key is one to one.
+        let mut a: Vec<(i32, char)> = orig.iter().map(|&v| (v, 'x')).collect();
+        running_sort_right_by_key(&mut a, run, |p| p.0, &mut RealCounter::default());
+        let mut keys = orig.clone();
+        running_sort_right(&mut keys, run, &mut RealCounter::default());
+        assert_eq!(a.iter().map(|p| p.0).collect::<Vec<_>>(), keys);
+    }
+}
+
+#[test]
+fn ties_under_a_comparator_are_kept_together() {
+    let mut a: Vec<(u8, usize)> = (0..40).map(|i| ((i % 3) as u8, i)).collect();
+    let mut cnt = RealCounter::default();
+    merge_sort_by(&mut a, 0, 40, |x, y| x.0.cmp(&y.0), &mut cnt);
+    assert!(a
+        .windows(2)
+        .all(|w| w[0].0.cmp(&w[1].0) != Ordering::Greater));
+    let mut ids: Vec<usize> = a.iter().map(|p| p.1).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, (0..40).collect::<Vec<_>>());
+}
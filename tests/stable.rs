@@ -0,0 +1,109 @@
+use cheap::{
+    is_sorted, stable_merge, stable_merge_by, stable_merge_by_key, stable_merge_sort,
+    stable_merge_sort_by_key, RealCounter,
+};
+use rand::prelude::{thread_rng, Rng};
+
+/*
+ * Records with a small key space, so most keys repeat many times. The tag is the original
+ * position, so a stable sort leaves tags ascending within each key.
+ */
+fn records(n: usize, keys: u32) -> Vec<(u32, usize)> {
+    let mut rng = thread_rng();
+    (0..n).map(|i| (rng.gen_range(0..keys), i)).collect()
+}
+
+#[test]
+fn stable_sort_keeps_ties_in_order() {
+    for &n in &[0, 1, 2, 5, 17, 64, 100, 1000] {
+        for &keys in &[1, 2, 3, 10] {
+            let mut a = records(n, keys);
+            let mut expect = a.clone();
+            expect.sort_by_key(|r| r.0);
+
+            let mut cnt = RealCounter::default();
+            stable_merge_sort_by_key(&mut a, 0, n, |r| r.0, &mut cnt);
+            assert_eq!(a, expect, "n={}, keys={}", n, keys);
+        }
+    }
+}
+
+#[test]
+fn stable_sort_of_subrange() {
+    let mut a = records(200, 4);
+    let orig = a.clone();
+    let mut expect = a.clone();
+    expect[50..150].sort_by_key(|r| r.0);
+
+    stable_merge_sort_by_key(&mut a, 50, 150, |r| r.0, &mut RealCounter::default());
+    assert_eq!(a, expect);
+    assert_eq!(a[..50], orig[..50]);
+    assert_eq!(a[150..], orig[150..]);
+}
+
+#[test]
+fn stable_merge_takes_left_run_first() {
+    for &(n, md) in &[(0, 0), (10, 0), (10, 10), (40, 13), (300, 150), (301, 7)] {
+        let mut a = records(n, 3);
+        a[..md].sort_by_key(|r| r.0);
+        a[md..].sort_by_key(|r| r.0);
+        let mut expect = a.clone();
+        expect.sort_by_key(|r| r.0);
+
+        stable_merge_by(
+            &mut a,
+            0,
+            md,
+            n,
+            |x, y| x.0.cmp(&y.0),
+            &mut RealCounter::default(),
+        );
+        assert_eq!(a, expect, "n={}, md={}", n, md);
+    }
+}
+
+#[test]
+fn stable_merge_by_key_keeps_ties_in_order() {
+    // All keys equal: the merge must leave the array exactly as it found it.
+    let mut a = records(50, 1);
+    let orig = a.clone();
+    stable_merge_by_key(&mut a, 0, 20, 50, |r| r.0, &mut RealCounter::default());
+    assert_eq!(a, orig);
+
+    // Ties across the two runs put the left run's elements first; only the subrange moves.
+    let mut b = vec![
+        (9, 0),
+        (2, 1),
+        (0, 2),
+        (1, 3),
+        (1, 4),
+        (0, 5),
+        (1, 6),
+        (3, 7),
+        (0, 8),
+    ];
+    let expect = vec![
+        (9, 0),
+        (2, 1),
+        (0, 2),
+        (0, 5),
+        (1, 3),
+        (1, 4),
+        (1, 6),
+        (3, 7),
+        (0, 8),
+    ];
+    stable_merge_by_key(&mut b, 2, 5, 8, |r| r.0, &mut RealCounter::default());
+    assert_eq!(b, expect);
+}
+
+#[test]
+fn stable_natural_order() {
+    let mut a: Vec<u32> = records(500, 7).into_iter().map(|r| r.0).collect();
+    stable_merge_sort(&mut a, 0, 500, &mut RealCounter::default());
+    assert!(is_sorted(&a, 0, 500));
+
+    let mut b = vec![1, 4, 4, 9, 0, 4, 5];
+    stable_merge(&mut b, 0, 4, 7, &mut RealCounter::default());
+    assert_eq!(b, vec![0, 1, 4, 4, 4, 5, 9]);
+}
@@ -0,0 +1,53 @@
+use cheap::{is_partially_sorted, partial_sort, partial_sort_by_key, select_top_k, RealCounter};
+
+#[test]
+fn partial_sort_edges_of_k() {
+    let orig: Vec<i64> = vec![5, -2, 9, 0, 0, 7, -2, 3, 1];
+    let mut sorted = orig.clone();
+    sorted.sort_unstable();
+    for k in 0..=orig.len() + 2 {
+        let mut a = orig.clone();
+        partial_sort(&mut a, k, &mut RealCounter::default());
+        let k = k.min(a.len());
+        assert!(is_partially_sorted(&a, k), "k={}", k);
+        assert_eq!(a[..k], sorted[..k], "k={}", k);
+        // Nothing is lost from the tail.
+        a.sort_unstable();
+        assert_eq!(a, sorted);
+    }
+
+    let mut same = vec![4; 20];
+    partial_sort(&mut same, 7, &mut RealCounter::default());
+    assert_eq!(same, vec![4; 20]);
+}
+
+#[test]
+fn a_small_k_costs_about_one_recenter() {
+    // Picking the best few shouldn't cost anything like a full sort.
+    let n = 4096;
+    let mut a: Vec<u32> = (0..n).map(|i| (i * 1103) % n).collect();
+    let mut cnt = RealCounter::default();
+    partial_sort(&mut a, 4, &mut cnt);
+    assert_eq!(a[..4], [0, 1, 2, 3]);
+    assert!(cnt.compares < 3 * n as u64, "{} compares", cnt.compares);
+}
+
+#[test]
+fn select_top_k_returns_the_smallest() {
+    let mut a: Vec<u32> = (0..100).map(|i| (i * 37 + 11) % 100).collect();
+    let top = select_top_k(&mut a, 5, &mut RealCounter::default());
+    assert_eq!(top, &[0, 1, 2, 3, 4]);
+
+    let mut b = vec![3, 1, 2];
+    assert_eq!(
+        select_top_k(&mut b, 10, &mut RealCounter::default()),
+        &[1, 2, 3]
+    );
+}
+
+#[test]
+fn partial_sort_by_key_ranks_by_the_key() {
+    let mut a: Vec<(char, i32)> = "heapsort".chars().zip((0..8).rev()).collect();
+    partial_sort_by_key(&mut a, 3, |p| p.1, &mut RealCounter::default());
+    assert_eq!(a[..3], [('t', 0), ('r', 1), ('o', 2)]);
+}
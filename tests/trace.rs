@@ -0,0 +1,163 @@
+use cheap::{
+    heap_sort_left, heap_sort_right, merge_sort, replay, Cheap, RealCounter, ReplayError, Tracer,
+};
+use rand::prelude::{thread_rng, SliceRandom};
+
+/*
+ * Merge sort a shuffle of `0..n`, returning the input and the trace.
+ */
+fn traced_merge_sort(n: usize) -> (Vec<u32>, Vec<String>) {
+    let mut a: Vec<u32> = (0..n as u32).collect();
+    a.shuffle(&mut thread_rng());
+    let input = a.clone();
+    let mut tr = Tracer::with_counter(Vec::new(), RealCounter::default());
+    merge_sort(&mut a, 0, n, Cheap::merge, &mut tr);
+    let (out, _) = tr.finish().unwrap();
+    let lines = String::from_utf8(out).unwrap();
+    (input, lines.lines().map(str::to_string).collect())
+}
+
+#[test]
+fn replay_reproduces_sorts() {
+    for &n in &[0, 1, 2, 5, 16, 100, 1000] {
+        let (input, trace) = traced_merge_sort(n);
+        let mut a = input.clone();
+        replay(&mut a, &trace).unwrap();
+        let mut expect = input;
+        expect.sort_unstable();
+        assert_eq!(a, expect);
+    }
+
+    let mut a: Vec<u32> = (0..300).collect();
+    a.shuffle(&mut thread_rng());
+    let mut b = a.clone();
+    let mut tr = Tracer::new(Vec::new());
+    heap_sort_left(&mut a, &mut tr);
+    let (out, _) = tr.finish().unwrap();
+    let stats = replay(&mut b, String::from_utf8(out).unwrap().lines()).unwrap();
+    assert_eq!(a, b);
+    assert!(stats.calls > 0);
+    assert_eq!(stats.reversals, 0);
+}
+
+#[test]
+fn replay_follows_heap_right_reversal() {
+    let mut a: Vec<u32> = (0..300).collect();
+    a.shuffle(&mut thread_rng());
+    let mut b = a.clone();
+    let mut tr = Tracer::new(Vec::new());
+    heap_sort_right(&mut a, &mut tr);
+    let (out, _) = tr.finish().unwrap();
+    let trace = String::from_utf8(out).unwrap();
+    assert_eq!(trace.lines().last(), Some("{\"reverse\":[0,300]}"));
+    let stats = replay(&mut b, trace.lines()).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(stats.reversals, 1);
+
+    // A reversal has to fit in the array.
+    let mut a: Vec<u32> = (0..4).collect();
+    match replay(&mut a, ["{\"reverse\":[1,5]}"]) {
+        Err(ReplayError::Malformed { line, .. }) => assert_eq!(line, 1),
+        r => panic!("expected a malformed line, got {:?}", r),
+    }
+}
+
+#[test]
+fn tracer_passes_counts_on() {
+    let mut a: Vec<u32> = (0..500).rev().collect();
+    let mut cnt = RealCounter::default();
+    merge_sort(&mut a, 0, 500, Cheap::merge, &mut cnt);
+
+    let mut b: Vec<u32> = (0..500).rev().collect();
+    let mut tr = Tracer::with_counter(Vec::new(), RealCounter::default());
+    merge_sort(&mut b, 0, 500, Cheap::merge, &mut tr);
+    let (out, traced) = tr.finish().unwrap();
+    assert_eq!((traced.compares, traced.swaps), (cnt.compares, cnt.swaps));
+
+    let swaps = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .filter(|l| l.starts_with("{\"swap\""))
+        .count();
+    assert_eq!(swaps as u64, cnt.swaps);
+}
+
+#[test]
+fn replay_finds_where_a_trace_diverges() {
+    let (input, mut trace) = traced_merge_sort(200);
+    let nested = (1..trace.len())
+        .find(|&i| trace[i].starts_with("{\"swap\"") && trace[i - 1].starts_with("{\"call\""))
+        .unwrap();
+    trace[nested] = "{\"swap\":[0,0]}".to_string();
+    match replay(&mut input.clone(), &trace) {
+        Err(ReplayError::Mismatch { line, .. }) => assert_eq!(line, nested + 1),
+        r => panic!("expected a mismatch, got {:?}", r),
+    }
+
+    trace.truncate(nested);
+    match replay(&mut input.clone(), &trace) {
+        Err(ReplayError::Truncated { .. }) => {}
+        r => panic!("expected the trace to run out, got {:?}", r),
+    }
+
+    let bad = [
+        "{\"swap\":[0,1]}",
+        "{\"call\":\"pop_left\",\"lo\":5,\"c\":1,\"hi\":9}",
+    ];
+    match replay(&mut input.clone(), bad) {
+        Err(ReplayError::Malformed { line, .. }) => assert_eq!(line, 2),
+        r => panic!("expected a malformed line, got {:?}", r),
+    }
+}
+
+#[test]
+fn replay_refuses_calls_that_cannot_start() {
+    // A center at `hi` of a non-empty heap is outside it.
+    let mut a: Vec<u32> = (0..10).collect();
+    let bad = ["{\"call\":\"pop_left\",\"lo\":0,\"c\":10,\"hi\":10}"];
+    match replay(&mut a, bad) {
+        Err(ReplayError::Malformed { line, .. }) => assert_eq!(line, 1),
+        r => panic!("expected a malformed line, got {:?}", r),
+    }
+    assert_eq!(a, (0..10).collect::<Vec<_>>());
+
+    // Descending values aren't a heap centered at the low end. Only debug builds check for
+    // that up front; release builds find the swaps don't match.
+    let mut a: Vec<u32> = (0..10).rev().collect();
+    let bad = [
+        "{\"swap\":[0,9]}",
+        "{\"call\":\"pop_left\",\"lo\":0,\"c\":0,\"hi\":10}",
+        "{\"swap\":[8,9]}",
+    ];
+    let expect = if cfg!(debug_assertions) { 2 } else { 3 };
+    match replay(&mut a, bad) {
+        Err(ReplayError::Mismatch { line, .. }) => assert_eq!(line, expect),
+        r => panic!("expected a mismatch, got {:?}", r),
+    }
+
+    // A merge needs sorted runs.
+    let mut a: Vec<u32> = vec![3, 1, 2, 0];
+    let bad = ["{\"call\":\"merge\",\"lo\":0,\"c\":2,\"hi\":4}"];
+    match replay(&mut a, bad) {
+        Err(ReplayError::Mismatch { line, .. }) => assert_eq!(line, 1),
+        r => panic!("expected a mismatch, got {:?}", r),
+    }
+}
+
+#[test]
+fn into_sorted_iter_traces_indices_into_the_whole_array() {
+    let mut a: Vec<u32> = (0..60).collect();
+    a.shuffle(&mut thread_rng());
+    let input = a.clone();
+    let mut tr = Tracer::new(Vec::new());
+    let mut ch = Cheap::new_range(&mut a, 10, 10, 60, &mut tr);
+    ch.recenter();
+    assert_eq!(ch.into_sorted_iter().count(), 50);
+    let (out, _) = tr.finish().unwrap();
+    let trace = String::from_utf8(out).unwrap();
+    assert!(trace.contains("{\"call\":\"pop_left\",\"lo\":59,\"c\":59,\"hi\":60}"));
+
+    let mut b = input;
+    replay(&mut b, trace.lines()).unwrap();
+    assert_eq!(a, b);
+}
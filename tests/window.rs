@@ -0,0 +1,62 @@
+use cheap::{RealCounter, SlidingWindow};
+
+fn mins(data: &[i32], width: usize) -> Vec<i32> {
+    let mut cnt = RealCounter::default();
+    SlidingWindow::min(data, width, &mut cnt).copied().collect()
+}
+
+fn maxes(data: &[i32], width: usize) -> Vec<i32> {
+    let mut cnt = RealCounter::default();
+    SlidingWindow::max(data, width, &mut cnt).copied().collect()
+}
+
+#[test]
+fn the_extreme_leaves_as_the_window_slides() {
+    // Rising data evicts the minimum every step; falling data evicts the maximum.
+    let rising: Vec<i32> = (0..10).collect();
+    assert_eq!(mins(&rising, 4), (0..7).collect::<Vec<_>>());
+    assert_eq!(maxes(&rising, 4), (3..10).collect::<Vec<_>>());
+    let falling: Vec<i32> = (0..10).rev().collect();
+    assert_eq!(mins(&falling, 4), (0..7).rev().collect::<Vec<_>>());
+    assert_eq!(maxes(&falling, 4), (3..10).rev().collect::<Vec<_>>());
+
+    let data = [5, -1, 8, 2, 2, 9, -3, 0, 7, 1, 6];
+    let expect_min: Vec<i32> = data.windows(3).map(|w| *w.iter().min().unwrap()).collect();
+    let expect_max: Vec<i32> = data.windows(3).map(|w| *w.iter().max().unwrap()).collect();
+    assert_eq!(mins(&data, 3), expect_min);
+    assert_eq!(maxes(&data, 3), expect_max);
+}
+
+#[test]
+fn widths_of_one_and_the_whole_slice() {
+    let data = [5, -1, 8, 2, 2, 9, -3, 0];
+    assert_eq!(mins(&data, 1), data.to_vec());
+    assert_eq!(maxes(&data, 1), data.to_vec());
+    assert_eq!(mins(&data, data.len()), vec![-3]);
+    assert_eq!(maxes(&data, data.len()), vec![9]);
+}
+
+#[test]
+fn ties_go_to_the_oldest() {
+    let data = [4, 1, 1, 3, 1, 5, 5];
+    let mut cnt = RealCounter::default();
+    let picks: Vec<usize> = SlidingWindow::min(&data, 3, &mut cnt)
+        .map(|v| {
+            (0..data.len())
+                .find(|&i| std::ptr::eq(v, &data[i]))
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(picks, vec![1, 1, 2, 4, 4]);
+}
+
+#[test]
+fn window_wider_than_data_yields_nothing() {
+    let data = [3, 1, 2];
+    let mut cnt = RealCounter::default();
+    let w = SlidingWindow::min(&data, 4, &mut cnt);
+    assert_eq!(w.len(), 0);
+    assert_eq!(w.count(), 0);
+    let mut cnt = RealCounter::default();
+    assert_eq!(SlidingWindow::max(&data, 3, &mut cnt).next(), Some(&3));
+}